@@ -17,15 +17,17 @@ if not (os.path.exists(PATH_STR) and os.path.isfile(PATH_STR)):
     print "ERROR: Provided argument is not a vaid file"
     exit(403)
 
+# pixels are stored as b, g, r, a: the byte order of argb8888 words in the
+# little endian memory the dma2d reads them from
 IMG = Image.open(PATH_STR)
 NUM_BANDS = len(IMG.getbands())
 with open(PATH_STR[:-3] + "dump", "wb") as out:
     if len(IMG.getbands()) > 3:
         for c in list(IMG.getdata()):
-            out.write(chr(c[0]) + chr(c[1]) + chr(c[2]) + chr(c[3]))
+            out.write(chr(c[2]) + chr(c[1]) + chr(c[0]) + chr(c[3]))
     else:
         for c in list(IMG.getdata()):
-            out.write(chr(c[0]) + chr(c[1]) + chr(c[2]) + chr(42))
+            out.write(chr(c[2]) + chr(c[1]) + chr(c[0]) + chr(42))
 
 
 print "Done"
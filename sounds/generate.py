@@ -0,0 +1,170 @@
+#!/usr/bin/env python3
+# -*- coding: utf-8 -*-
+
+# synthesizes the sound effects and the mariachi tune of the game
+#
+# all sounds are mono at 16 kHz, the rate the codec runs at. short effects
+# are stored as raw 16 bit little endian pcm (.pcm), longer ones as ima
+# adpcm (.adpcm): 4 bit codes, low nibble first, decoding starts with a
+# predictor of 0 and a step index of 0.
+
+import math
+import random
+import struct
+
+RATE = 16000
+
+INDEX_TABLE = [-1, -1, -1, -1, 2, 4, 6, 8] * 2
+STEP_TABLE = [
+    7, 8, 9, 10, 11, 12, 13, 14, 16, 17, 19, 21, 23, 25, 28, 31, 34, 37, 41,
+    45, 50, 55, 60, 66, 73, 80, 88, 97, 107, 118, 130, 143, 157, 173, 190, 209,
+    230, 253, 279, 307, 337, 371, 408, 449, 494, 544, 598, 658, 724, 796, 876,
+    963, 1060, 1166, 1282, 1411, 1552, 1707, 1878, 2066, 2272, 2499, 2749,
+    3024, 3327, 3660, 4026, 4428, 4871, 5358, 5894, 6484, 7132, 7845, 8630,
+    9493, 10442, 11487, 12635, 13899, 15289, 16818, 18500, 20350, 22385,
+    24623, 27086, 29794, 32767,
+]
+
+
+def clamp(value, low, high):
+    return max(low, min(high, value))
+
+
+def to_int(samples):
+    return [int(clamp(s, -1.0, 1.0) * 32767) for s in samples]
+
+
+def write_pcm(name, samples):
+    with open(name + ".pcm", "wb") as out:
+        for s in to_int(samples):
+            out.write(struct.pack("<h", s))
+
+
+def write_adpcm(name, samples):
+    predictor = 0
+    index = 0
+    codes = []
+    for s in to_int(samples):
+        step = STEP_TABLE[index]
+        diff = s - predictor
+        code = 0
+        if diff < 0:
+            code = 8
+            diff = -diff
+        delta = step >> 3
+        if diff >= step:
+            code |= 4
+            diff -= step
+            delta += step
+        if diff >= step >> 1:
+            code |= 2
+            diff -= step >> 1
+            delta += step >> 1
+        if diff >= step >> 2:
+            code |= 1
+            delta += step >> 2
+        # track the decoder, so the errors do not add up
+        predictor += -delta if code & 8 else delta
+        predictor = clamp(predictor, -32768, 32767)
+        index = clamp(index + INDEX_TABLE[code], 0, len(STEP_TABLE) - 1)
+        codes.append(code)
+    if len(codes) % 2:
+        codes.append(0)
+    with open(name + ".adpcm", "wb") as out:
+        for i in range(0, len(codes), 2):
+            out.write(struct.pack("B", codes[i] | (codes[i + 1] << 4)))
+
+
+def envelope(i, length, attack, release):
+    attack = int(attack * RATE)
+    release = int(release * RATE)
+    if i < attack:
+        return i / float(attack)
+    if i > length - release:
+        return (length - i) / float(release)
+    return 1.0
+
+
+def tone(freq, seconds, volume=0.5, harmonics=(1.0,), attack=0.005, release=0.02):
+    length = int(seconds * RATE)
+    out = []
+    for i in range(length):
+        t = i / float(RATE)
+        s = sum(a * math.sin(2 * math.pi * freq * (n + 1) * t)
+                for n, a in enumerate(harmonics))
+        out.append(volume * s / sum(harmonics) * envelope(i, length, attack, release))
+    return out
+
+
+def sweep(start, end, seconds, volume=0.5, noise=0.0):
+    length = int(seconds * RATE)
+    out = []
+    phase = 0.0
+    for i in range(length):
+        freq = start + (end - start) * i / float(length)
+        phase += 2 * math.pi * freq / RATE
+        s = math.sin(phase) + noise * random.uniform(-1, 1)
+        decay = 1.0 - i / float(length)
+        out.append(volume * s / (1 + noise) * decay)
+    return out
+
+
+def mix(*tracks):
+    length = max(len(t) for t in tracks)
+    return [sum(t[i] for t in tracks if i < len(t)) for i in range(length)]
+
+
+def note(name):
+    names = ["C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B"]
+    semitone = names.index(name[:-1]) + 12 * (int(name[-1]) + 1)
+    return 440.0 * 2 ** ((semitone - 69) / 12.0)
+
+
+# bright and brassy, like a mariachi trumpet
+TRUMPET = (1.0, 0.7, 0.5, 0.3, 0.2)
+
+
+def melody(notes, beat, harmonics, volume):
+    out = []
+    for name, beats in notes:
+        if name is None:
+            out += [0.0] * int(beats * beat * RATE)
+        else:
+            out += tone(note(name), beats * beat, volume, harmonics, 0.01, 0.04)
+    return out
+
+
+def mariachi():
+    beat = 0.18
+    tune = [("C4", 0.5), ("C4", 0.5), ("C4", 0.5), ("F4", 1.5), ("A4", 1),
+            ("C4", 0.5), ("C4", 0.5), ("C4", 0.5), ("F4", 1.5), ("A4", 1),
+            ("F4", 0.5), ("F4", 0.5), ("E4", 0.5), ("E4", 0.5), ("D4", 0.5),
+            ("D4", 0.5), ("C4", 2),
+            ("C4", 0.5), ("C4", 0.5), ("C4", 0.5), ("E4", 1.5), ("G4", 1),
+            ("C4", 0.5), ("C4", 0.5), ("C4", 0.5), ("E4", 1.5), ("G4", 1),
+            ("C5", 0.5), ("D5", 0.5), ("C5", 0.5), ("A#4", 0.5), ("A4", 0.5),
+            ("G4", 0.5), ("F4", 2)]
+    beats = sum(b for _, b in tune)
+    # oom-pah bass, one bar of f and one of c in turn
+    bass = []
+    bar = 0
+    while len(bass) < int(beats * beat * RATE):
+        root = "F2" if bar % 2 == 0 else "C2"
+        fifth = "C3" if bar % 2 == 0 else "G2"
+        bass += melody([(root, 1), (fifth, 1)], beat, (1.0, 0.3), 0.25)
+        bar += 1
+    return mix(melody(tune, beat, TRUMPET, 0.45), bass[:int(beats * beat * RATE)])
+
+
+def game_over():
+    return melody([("G4", 1), ("F#4", 1), ("F4", 1), ("E4", 3)], 0.25, TRUMPET, 0.6)
+
+
+random.seed(2017)
+write_pcm("hit", sweep(900, 200, 0.15, 0.8, 0.6))
+write_pcm("miss", mix(tone(110, 0.25, 0.4, (1.0, 0.5, 0.3)), tone(116, 0.25, 0.4)))
+write_pcm("beep", tone(1000, 0.08, 0.5))
+write_adpcm("game_over", game_over())
+write_adpcm("mariachi", mariachi())
+
+print("Done")
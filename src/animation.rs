@@ -0,0 +1,134 @@
+// frame based sprite animations
+//
+// frames do not carry images of their own but transform the image of the
+// sprite they are played on, so the same animation works for every target.
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Frame {
+    // displacement of the image, it stays clipped to the sprite's rectangle
+    pub offset: (i32, i32),
+    // visible share of the pixels in sixteenths, thinned out by dithering
+    pub coverage: u8,
+    pub duration: usize,
+}
+
+pub struct Animation {
+    pub frames: &'static [Frame],
+    pub looping: bool,
+    // played once a non looping animation is finished
+    pub next: Option<&'static Animation>,
+}
+
+pub const FULL_COVERAGE: u8 = 16;
+
+// how sprites look without an animation
+pub const STILL: Frame = Frame {
+    offset: (0, 0),
+    coverage: FULL_COVERAGE,
+    duration: 0,
+};
+
+pub static POP_IN: Animation = Animation {
+    frames: &[Frame { offset: (0, 6), coverage: 4, duration: 60 },
+              Frame { offset: (0, 3), coverage: 8, duration: 60 },
+              Frame { offset: (0, 1), coverage: 12, duration: 60 }],
+    looping: false,
+    next: Some(&IDLE_WOBBLE),
+};
+
+pub static IDLE_WOBBLE: Animation = Animation {
+    frames: &[Frame { offset: (0, 0), coverage: FULL_COVERAGE, duration: 400 },
+              Frame { offset: (1, 0), coverage: FULL_COVERAGE, duration: 150 },
+              Frame { offset: (0, 0), coverage: FULL_COVERAGE, duration: 400 },
+              Frame { offset: (-1, 0), coverage: FULL_COVERAGE, duration: 150 }],
+    looping: true,
+    next: None,
+};
+
+pub static POP_OUT: Animation = Animation {
+    frames: &[Frame { offset: (0, 1), coverage: 12, duration: 60 },
+              Frame { offset: (0, 3), coverage: 8, duration: 60 },
+              Frame { offset: (0, 6), coverage: 4, duration: 60 }],
+    looping: false,
+    next: None,
+};
+
+pub static EXPLODE: Animation = Animation {
+    frames: &[Frame { offset: (2, 0), coverage: 14, duration: 50 },
+              Frame { offset: (-2, 1), coverage: 10, duration: 50 },
+              Frame { offset: (1, -2), coverage: 6, duration: 70 },
+              Frame { offset: (-1, 2), coverage: 3, duration: 70 },
+              Frame { offset: (0, 0), coverage: 1, duration: 70 }],
+    looping: false,
+    next: None,
+};
+
+impl Animation {
+    pub fn duration(&self) -> usize {
+        self.frames.iter().map(|f| f.duration).sum()
+    }
+
+    // index of the frame shown `elapsed` ms after the start, None once a non
+    // looping animation is over
+    pub fn frame_index(&self, elapsed: usize) -> Option<usize> {
+        let duration = self.duration();
+        if duration == 0 {
+            return None;
+        }
+        let mut t = if self.looping {
+            elapsed % duration
+        } else if elapsed < duration {
+            elapsed
+        } else {
+            return None;
+        };
+        for (i, frame) in self.frames.iter().enumerate() {
+            if t < frame.duration {
+                return Some(i);
+            }
+            t -= frame.duration;
+        }
+        None
+    }
+}
+
+// an animation being played, started at `start` on the game clock
+#[derive(Clone, Copy)]
+pub struct AnimationState {
+    animation: &'static Animation,
+    start: usize,
+}
+
+impl AnimationState {
+    pub fn new(animation: &'static Animation, start: usize) -> Self {
+        AnimationState {
+            animation: animation,
+            start: start,
+        }
+    }
+
+    // advances to the follow-up animation if needed and returns the current
+    // frame, None once everything is played
+    pub fn update(&mut self, tick: usize) -> Option<Frame> {
+        loop {
+            let elapsed = tick.wrapping_sub(self.start);
+            if let Some(i) = self.animation.frame_index(elapsed) {
+                return Some(self.animation.frames[i]);
+            }
+            match self.animation.next {
+                Some(next) => {
+                    self.start += self.animation.duration();
+                    self.animation = next;
+                }
+                None => return None,
+            }
+        }
+    }
+}
+
+// 4x4 ordered dither, a pixel is drawn if its threshold is below the coverage
+const BAYER_4X4: [[u8; 4]; 4] = [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+
+pub fn is_covered(x: i32, y: i32, coverage: u8) -> bool {
+    BAYER_4X4[(y & 3) as usize][(x & 3) as usize] < coverage
+}
@@ -0,0 +1,231 @@
+// tells claps, whistles and sustained vowels apart
+//
+// a sound starts with a shot of the detector and is followed window by
+// window. claps are over quickly. longer sounds are a whistle if most of
+// their energy sits in a single high frequency, measured with goertzel
+// filters, and a voice otherwise; voices also cross zero a lot less often.
+use math;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Sound {
+    Clap,
+    Whistle,
+    Voice,
+}
+
+#[derive(Clone, Copy)]
+pub struct ClassifierConfig {
+    // of one channel, in Hz
+    pub sample_rate: u32,
+    // sounds that end before this many ms are claps
+    pub clap_max: usize,
+    // whistles put at least this percentage of their energy into one of the
+    // whistle frequencies
+    pub whistle_tonality: u32,
+    // and cross zero at least this many times per 1000 samples
+    pub whistle_crossings: u32,
+}
+
+// whistle frequencies checked by the goertzel filters, in Hz
+const WHISTLE_BINS: [u32; 9] = [1000, 1250, 1500, 1750, 2000, 2250, 2500, 2750, 3000];
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum State {
+    Idle,
+    Listening,
+    // a voice keeps being reported until it ends
+    Voice,
+}
+
+pub struct Classifier {
+    config: ClassifierConfig,
+    state: State,
+    // frames of the current sound seen so far
+    frames: u32,
+    windows: u32,
+    crossings: u32,
+    tonality: u32,
+}
+
+impl Classifier {
+    pub fn new(config: ClassifierConfig) -> Self {
+        Classifier {
+            config: config,
+            state: State::Idle,
+            frames: 0,
+            windows: 0,
+            crossings: 0,
+            tonality: 0,
+        }
+    }
+
+    // a new sound began with the last window
+    pub fn start(&mut self) {
+        self.state = State::Listening;
+        self.frames = 0;
+        self.windows = 0;
+        self.crossings = 0;
+        self.tonality = 0;
+    }
+
+    // follows the sound with the next window of interleaved samples;
+    // `loud` tells whether the sound is still going on. returns the kind of
+    // sound once it is known, and for voices in every window until they end.
+    pub fn add(&mut self, window: &[i16], channels: usize, loud: bool) -> Option<Sound> {
+        match self.state {
+            State::Idle => return None,
+            State::Voice => {
+                if loud {
+                    return Some(Sound::Voice);
+                }
+                self.state = State::Idle;
+                return None;
+            }
+            State::Listening => {}
+        }
+        if !loud {
+            self.state = State::Idle;
+            // over before it could be told apart, so it was short
+            return Some(Sound::Clap);
+        }
+        self.frames += (window.len() / channels) as u32;
+        self.windows += 1;
+        self.crossings += zero_crossings(window, channels);
+        self.tonality += tonality(window, channels, self.config.sample_rate);
+        let duration = self.frames as usize * 1000 / self.config.sample_rate as usize;
+        if duration < self.config.clap_max {
+            return None;
+        }
+        let crossings = self.crossings / self.windows;
+        let tonality = self.tonality / self.windows;
+        if tonality >= self.config.whistle_tonality &&
+           crossings >= self.config.whistle_crossings {
+            self.state = State::Idle;
+            Some(Sound::Whistle)
+        } else {
+            self.state = State::Voice;
+            Some(Sound::Voice)
+        }
+    }
+}
+
+// sign changes of the first channel per 1000 samples
+pub fn zero_crossings(window: &[i16], channels: usize) -> u32 {
+    let mut crossings = 0;
+    let mut samples = 0;
+    let mut previous: Option<bool> = None;
+    for frame in window.chunks(channels) {
+        let s = frame[0] as i64;
+        let positive = s >= 0;
+        if let Some(p) = previous {
+            if p != positive {
+                crossings += 1;
+            }
+        }
+        previous = Some(positive);
+        samples += 1;
+    }
+    if samples < 2 {
+        return 0;
+    }
+    crossings * 1000 / (samples - 1)
+}
+
+// percentage of the energy of the first channel that lies in the strongest
+// whistle frequency
+pub fn tonality(window: &[i16], channels: usize, sample_rate: u32) -> u32 {
+    let mut energy: i64 = 0;
+    let mut n: i64 = 0;
+    for frame in window.chunks(channels) {
+        let s = frame[0] as i64;
+        energy += s * s;
+        n += 1;
+    }
+    if energy == 0 {
+        return 0;
+    }
+    let mut best = 0;
+    for f in &WHISTLE_BINS {
+        let power = goertzel(window, channels, *f, sample_rate);
+        if power > best {
+            best = power;
+        }
+    }
+    // a pure tone on the bin frequency has a power of n / 2 times its energy
+    let percent = best * 100 / (energy * n / 2);
+    if percent > 100 { 100 } else { percent as u32 }
+}
+
+// power of `frequency` in the first channel
+fn goertzel(window: &[i16], channels: usize, frequency: u32, sample_rate: u32) -> i64 {
+    let angle = (360 * frequency as u64 / sample_rate as u64) as i32;
+    // 2 cos(w) in 2.14 fixed point
+    let coeff = 2 * math::cos_deg(angle) as i64;
+    let (mut s1, mut s2): (i64, i64) = (0, 0);
+    for frame in window.chunks(channels) {
+        let s = frame[0] as i64;
+        let s0 = s + ((coeff * s1) >> 14) - s2;
+        s2 = s1;
+        s1 = s0;
+    }
+    s1 * s1 + s2 * s2 - (((coeff * s1) >> 14) * s2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use collections::vec::Vec;
+    use constants;
+    use detector::ShotDetector;
+    use wav::Wav;
+
+    static CLAPS: &'static [u8] = include_bytes!("../tests/fixtures/claps.wav");
+    static WHISTLE: &'static [u8] = include_bytes!("../tests/fixtures/whistle.wav");
+    static VOICE: &'static [u8] = include_bytes!("../tests/fixtures/voice.wav");
+
+    // follows every shot in the recording like the microphone does and
+    // returns what they were told apart as, each voice once
+    fn sounds(recording: &[u8]) -> Vec<Sound> {
+        let wav = Wav::parse(recording).unwrap();
+        let mut detector = ShotDetector::new(constants::SHOT_DETECTOR);
+        let mut classifier = Classifier::new(constants::SOUND_CLASSIFIER);
+        let mut window = [0; constants::MIC_WINDOW];
+        let mut start = 0;
+        for _ in 0..constants::MIC_CALIBRATION_WINDOWS {
+            wav.read(start, &mut window);
+            detector.calibrate(&window);
+            start += window.len();
+        }
+        let mut sounds = Vec::new();
+        while wav.read(start, &mut window) == window.len() {
+            let frames = start / constants::MIC_CHANNELS;
+            let tick = frames * 1000 / constants::MIC_SAMPLE_RATE as usize;
+            if detector.process(&window, tick) {
+                classifier.start();
+            }
+            let heard = classifier.add(&window, constants::MIC_CHANNELS, detector.is_active());
+            if let Some(sound) = heard {
+                if sound != Sound::Voice || sounds.last() != Some(&Sound::Voice) {
+                    sounds.push(sound);
+                }
+            }
+            start += window.len();
+        }
+        sounds
+    }
+
+    #[test]
+    fn claps() {
+        assert_eq!(sounds(CLAPS), [Sound::Clap, Sound::Clap, Sound::Clap]);
+    }
+
+    #[test]
+    fn whistle_at_the_lowest_bin() {
+        assert_eq!(sounds(WHISTLE), [Sound::Whistle]);
+    }
+
+    #[test]
+    fn voice() {
+        assert_eq!(sounds(VOICE), [Sound::Voice]);
+    }
+}
@@ -0,0 +1,113 @@
+// colors are stored as 8 bit argb and converted to the framebuffer format
+// when they are drawn
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Color {
+    pub a: u8,
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+// palette
+pub const TRANSPARENT: Color = Color { a: 0, r: 0, g: 0, b: 0 };
+pub const BLACK: Color = Color { a: 255, r: 0, g: 0, b: 0 };
+pub const WHITE: Color = Color { a: 255, r: 255, g: 255, b: 255 };
+pub const RED: Color = Color { a: 255, r: 255, g: 0, b: 0 };
+pub const GREEN: Color = Color { a: 255, r: 0, g: 255, b: 0 };
+pub const BLUE: Color = Color { a: 255, r: 0, g: 0, b: 255 };
+pub const YELLOW: Color = Color { a: 255, r: 255, g: 255, b: 0 };
+pub const ORANGE: Color = Color { a: 255, r: 255, g: 165, b: 0 };
+pub const PINK: Color = Color { a: 255, r: 255, g: 105, b: 180 };
+pub const PURPLE: Color = Color { a: 255, r: 160, g: 32, b: 240 };
+
+// pics/convert.py marks opaque pixels with 42, so everything from there on
+// counts as visible in the 1 bit alpha formats
+const ALPHA_THRESHOLD: u8 = 42;
+
+impl Color {
+    pub fn rgb(r: u8, g: u8, b: u8) -> Self {
+        Self::argb(255, r, g, b)
+    }
+
+    pub fn argb(a: u8, r: u8, g: u8, b: u8) -> Self {
+        Color {
+            a: a,
+            r: r,
+            g: g,
+            b: b,
+        }
+    }
+
+    pub fn from_hex(color: u32) -> Self {
+        Self::from_argb8888(0xFF00_0000 | color)
+    }
+
+    pub fn from_argb8888(color: u32) -> Self {
+        Self::argb((color >> 24) as u8,
+                   (color >> 16) as u8,
+                   (color >> 8) as u8,
+                   color as u8)
+    }
+
+    pub fn from_argb1555(color: u16) -> Self {
+        let a = if color & (1 << 15) != 0 { 255 } else { 0 };
+        Self::argb(a,
+                   expand_5_bits(color >> 10),
+                   expand_5_bits(color >> 5),
+                   expand_5_bits(color))
+    }
+
+    pub fn from_rgb565(color: u16) -> Self {
+        Self::rgb(expand_5_bits(color >> 11),
+                  expand_6_bits(color >> 5),
+                  expand_5_bits(color))
+    }
+
+    pub fn to_argb8888(&self) -> u32 {
+        ((self.a as u32) << 24) | ((self.r as u32) << 16) | ((self.g as u32) << 8) |
+        (self.b as u32)
+    }
+
+    pub fn to_argb1555(&self) -> u16 {
+        let a: u16 = if self.a >= ALPHA_THRESHOLD { 1 << 15 } else { 0 };
+        a | ((self.r as u16 >> 3) << 10) | ((self.g as u16 >> 3) << 5) | (self.b as u16 >> 3)
+    }
+
+    pub fn to_rgb565(&self) -> u16 {
+        ((self.r as u16 >> 3) << 11) | ((self.g as u16 >> 2) << 5) | (self.b as u16 >> 3)
+    }
+
+    pub fn with_alpha(&self, a: u8) -> Self {
+        Self::argb(a, self.r, self.g, self.b)
+    }
+
+    // linear interpolation, `amount` 0 gives self and 255 gives `other`
+    pub fn blend(&self, other: Color, amount: u8) -> Self {
+        Self::argb(mix(self.a, other.a, amount),
+                   mix(self.r, other.r, amount),
+                   mix(self.g, other.g, amount),
+                   mix(self.b, other.b, amount))
+    }
+
+    // draws self with its alpha on top of an opaque `background`
+    pub fn over(&self, background: Color) -> Self {
+        background.blend(self.with_alpha(255), self.a)
+    }
+}
+
+fn mix(from: u8, to: u8, amount: u8) -> u8 {
+    let from = from as u32;
+    let to = to as u32;
+    let amount = amount as u32;
+    ((from * (255 - amount) + to * amount) / 255) as u8
+}
+
+fn expand_5_bits(value: u16) -> u8 {
+    let v = (value & 0x1F) as u8;
+    (v << 3) | (v >> 2)
+}
+
+fn expand_6_bits(value: u16) -> u8 {
+    let v = (value & 0x3F) as u8;
+    (v << 2) | (v >> 4)
+}
@@ -1,3 +1,9 @@
+use color::{self, Color};
+use score::{Score, ScorePolicy};
+use detector::DetectorConfig;
+use classifier::ClassifierConfig;
+use sound::{Encoding, Sample};
+
 //pictures
 pub static FIESTA_PINATA: &'static [u8] = include_bytes!("../pics/name.dump");
 pub static TRUMP: &'static [u8] = include_bytes!("../pics/trump_cartoon.dump");
@@ -12,6 +18,28 @@ pub static BURGER_MODE: &'static [u8] = include_bytes!("../pics/burger.dump");
 pub static SILENT_BTN_NEG: &'static [u8] = include_bytes!("../pics/mic_on.dump");
 pub static SILENT_BTN: &'static [u8] = include_bytes!("../pics/mic_off.dump");
 
+// sounds, see sounds/generate.py
+pub static HIT_SOUND: Sample = Sample {
+    data: include_bytes!("../sounds/hit.pcm"),
+    encoding: Encoding::Pcm16,
+};
+pub static MISS_SOUND: Sample = Sample {
+    data: include_bytes!("../sounds/miss.pcm"),
+    encoding: Encoding::Pcm16,
+};
+pub static BEEP_SOUND: Sample = Sample {
+    data: include_bytes!("../sounds/beep.pcm"),
+    encoding: Encoding::Pcm16,
+};
+pub static GAME_OVER_SOUND: Sample = Sample {
+    data: include_bytes!("../sounds/game_over.adpcm"),
+    encoding: Encoding::ImaAdpcm,
+};
+pub static MARIACHI_MUSIC: Sample = Sample {
+    data: include_bytes!("../sounds/mariachi.adpcm"),
+    encoding: Encoding::ImaAdpcm,
+};
+
 // renderer
 pub const FIESTA_SIZE: (u16, u16) = (480, 53);
 pub const DISPLAY_SIZE: (u16, u16) = (480, 272);
@@ -19,8 +47,14 @@ pub const GAME_OVER_SIZE: (u16, u16) = (480, 64);
 pub const GAME_OVER_OFFSET_Y: u16 = 90;
 pub const START_SIZE: (u16, u16) = (480, 74);
 pub const SILENT_BTN_SIZE: (u16, u16) = (40, 40);
+pub const SOUND_SETTING_SIZE: (u16, u16) = (120, 30);
+// right next to the silent button
+pub const LEVEL_METER_SIZE: (u16, u16) = (6, 40);
+pub const LEVEL_METER_GAP: u16 = 4;
 pub const GAME_MODE_BTN_SIZE: (u16, u16) = (80, 74);
 pub const TARGET_SIZE: (u16, u16) = (50, 50);
+pub const DOUBLE_BUFFERING: bool = true;
+pub const TRANSITION_DURATION: usize = 600;
 
 // seven_segment
 pub const HEIGHT_TO_WIDTH_SCALING: u16 = 2;
@@ -29,18 +63,67 @@ pub const ELEMENT_GAP_SMALL: u16 = 3;
 pub const ELEMENT_WIDTH_BIG: u16 = 24;
 pub const ELEMENT_GAP_BIG: u16 = ELEMENT_GAP_SMALL;
 pub const HS_SPACE_SIZE: u16 = 7;
+pub const SCORE_DIGITS: usize = 5;
+// room for mm:ss
+pub const COUNTDOWN_DIGITS: usize = 4;
 
 // colors
-pub const RED: u16 = 0xFC00;
-pub const GREEN: u16 = 0x83E0;
-pub const BLACK: u16 = 0x8000;
+pub const RED: Color = color::RED;
+pub const GREEN: Color = color::GREEN;
+pub const BLACK: Color = color::BLACK;
 
 // game constants
 pub const GAME_TIME: u16 = 30;
+pub const COUNTDOWN_TENTHS_BELOW: u32 = 10_000;
 pub const MAX_EVIL_TARGETS: usize = 4;
 pub const MAX_HERO_TARGETS: usize = 6;
-pub const HERO_POINTS: u16 = 70;
-pub const EVIL_POINTS: u16 = 50;
-pub const SUPER_EVIL_POINTS: u16 = 100;
+pub const HERO_POINTS: Score = 70;
+pub const EVIL_POINTS: Score = 50;
+pub const SUPER_EVIL_POINTS: Score = 100;
+pub const SCORE_POLICY: ScorePolicy = ScorePolicy::ClampAtZero;
 pub const SUPER_TARGET_HIDING_DURATION: (usize, usize) = (5000, 10000);
-pub const VOLUME_THRESHOLD: u16 = 2000;
+// samples of both channels in the capture ring and per analysed window
+pub const MIC_RING: usize = 2048;
+pub const MIC_WINDOW: usize = 64;
+pub const MIC_CHANNELS: usize = 2;
+pub const MIC_SAMPLE_RATE: u32 = 16000;
+pub const MIC_CALIBRATION_WINDOWS: usize = 64;
+// samples of both channels in the playback ring and mixed ahead of the dma,
+// the codec plays at the rate of the microphone
+pub const SPEAKER_RING: usize = 4096;
+pub const SPEAKER_LEAD: usize = 2048;
+pub const SPEAKER_CHANNELS: usize = MIC_CHANNELS;
+// sound settings at start up, independent of the silent mode of the
+// microphone; the sound setting of the start screen changes them later
+pub const SOUND_ENABLED: bool = true;
+// of the codec, 57 is 0 dB and 63 the maximum of +6 dB
+pub const HEADPHONE_VOLUME: u8 = 57;
+pub const MASTER_VOLUME: u8 = 200;
+// master volumes the sound setting steps through before turning sound off
+pub const SOUND_VOLUMES: [u8; 3] = [80, 140, MASTER_VOLUME];
+pub const EFFECT_VOLUME: u8 = 255;
+pub const MUSIC_VOLUME: u8 = 110;
+// the countdown beeps during the last seconds
+pub const COUNTDOWN_BEEPS_BELOW: u16 = 6;
+pub const SHOT_DETECTOR: DetectorConfig = DetectorConfig {
+    // rms a shot has to be louder than the ambient noise
+    margin: 600,
+    attack_shift: 1,
+    decay_shift: 3,
+    refractory: 150,
+};
+pub const SOUND_CLASSIFIER: ClassifierConfig = ClassifierConfig {
+    sample_rate: MIC_SAMPLE_RATE,
+    // a clap is over before this many ms
+    clap_max: 120,
+    whistle_tonality: 60,
+    // the lowest whistle of 1000 Hz crosses zero 125 times
+    whistle_crossings: 110,
+};
+// a whistle also hits targets this many px around the touch
+pub const BLAST_RADIUS: u16 = 60;
+// ms between two shots while rapid firing
+pub const RAPID_FIRE_INTERVAL: usize = 100;
+// ms before a button reacts to another touch
+pub const BUTTON_DEBOUNCE: usize = 250;
+pub const BURST_PARTICLES: usize = 24;
@@ -0,0 +1,174 @@
+// turns windows of microphone samples into single shots
+//
+// the rms level of every window drives an envelope with a fast attack and a
+// slower decay. a shot fires when the envelope rises above the noise floor
+// by the margin, and the detector only re-arms once the envelope fell back
+// to half the margin and the refractory period is over, so one clap gives
+// exactly one shot.
+use math;
+
+// levels are kept in 1/16, so slow averages do not get stuck
+const LEVEL_SHIFT: u32 = 4;
+// weight of a quiet window in the running noise floor, as a power of two
+const FLOOR_ADAPT_SHIFT: u32 = 6;
+
+#[derive(Clone, Copy)]
+pub struct DetectorConfig {
+    // rms level a shot needs above the noise floor
+    pub margin: u16,
+    // the envelope moves 1/2^shift of the way to the new level per window
+    pub attack_shift: u32,
+    pub decay_shift: u32,
+    // minimum time between two shots, in ms
+    pub refractory: usize,
+}
+
+pub struct ShotDetector {
+    config: DetectorConfig,
+    noise_floor: u32,
+    envelope: u32,
+    armed: bool,
+    last_shot: Option<usize>,
+    calibration_windows: u32,
+}
+
+impl ShotDetector {
+    pub fn new(config: DetectorConfig) -> Self {
+        ShotDetector {
+            config: config,
+            noise_floor: 0,
+            envelope: 0,
+            armed: true,
+            last_shot: None,
+            calibration_windows: 0,
+        }
+    }
+
+    // starts a new noise floor measurement, feed it with `calibrate`
+    pub fn reset_calibration(&mut self) {
+        self.calibration_windows = 0;
+        self.noise_floor = 0;
+    }
+
+    // adds a window of ambient noise to the mean noise floor
+    pub fn calibrate(&mut self, window: &[i16]) {
+        let level = (rms(window) as u32) << LEVEL_SHIFT;
+        self.calibration_windows += 1;
+        let n = self.calibration_windows as i64;
+        let floor = self.noise_floor as i64;
+        self.noise_floor = (floor + (level as i64 - floor) / n) as u32;
+        self.envelope = self.noise_floor;
+    }
+
+    pub fn noise_floor(&self) -> u16 {
+        (self.noise_floor >> LEVEL_SHIFT) as u16
+    }
+
+    pub fn envelope(&self) -> u16 {
+        (self.envelope >> LEVEL_SHIFT) as u16
+    }
+
+    // envelope level a shot has to exceed
+    pub fn threshold(&self) -> u16 {
+        let threshold = self.noise_floor() as u32 + self.config.margin as u32;
+        if threshold > u16::max_value() as u32 {
+            u16::max_value()
+        } else {
+            threshold as u16
+        }
+    }
+
+    // true from a shot until the detector re-armed, i.e. while the sound lasts
+    pub fn is_active(&self) -> bool {
+        !self.armed
+    }
+
+    // processes the next window, recorded at `tick`, and returns true if it
+    // starts a shot
+    pub fn process(&mut self, window: &[i16], tick: usize) -> bool {
+        let level = (rms(window) as u32) << LEVEL_SHIFT;
+        if level > self.envelope {
+            self.envelope += (level - self.envelope) >> self.config.attack_shift;
+        } else {
+            self.envelope -= (self.envelope - level) >> self.config.decay_shift;
+        }
+
+        let margin = (self.config.margin as u32) << LEVEL_SHIFT;
+        let threshold = self.noise_floor + margin;
+        let resting = match self.last_shot {
+            Some(t) => tick.wrapping_sub(t) >= self.config.refractory,
+            None => true,
+        };
+        if self.armed && resting && self.envelope > threshold {
+            self.armed = false;
+            self.last_shot = Some(tick);
+            return true;
+        }
+        if self.envelope < self.noise_floor + margin / 2 {
+            self.armed = true;
+            // only quiet windows teach us how loud the room is
+            self.noise_floor = self.noise_floor - (self.noise_floor >> FLOOR_ADAPT_SHIFT) +
+                               (level >> FLOOR_ADAPT_SHIFT);
+        }
+        false
+    }
+}
+
+// root mean square of the samples
+pub fn rms(window: &[i16]) -> u16 {
+    if window.is_empty() {
+        return 0;
+    }
+    let mut sum: u64 = 0;
+    for s in window {
+        let s = *s as i64;
+        sum += (s * s) as u64;
+    }
+    math::isqrt((sum / window.len() as u64) as u32) as u16
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use constants;
+    use wav::Wav;
+
+    static CLAPS: &'static [u8] = include_bytes!("../tests/fixtures/claps.wav");
+    static NOISE: &'static [u8] = include_bytes!("../tests/fixtures/noise.wav");
+
+    // listens to the room at the start of the recording like the start
+    // screen does and counts the shots in the rest of it
+    fn shots(recording: &[u8]) -> usize {
+        let wav = Wav::parse(recording).unwrap();
+        assert_eq!(wav.channels as usize, constants::MIC_CHANNELS);
+        assert_eq!(wav.sample_rate, constants::MIC_SAMPLE_RATE);
+        let mut detector = ShotDetector::new(constants::SHOT_DETECTOR);
+        let mut window = [0; constants::MIC_WINDOW];
+        let mut start = 0;
+        for _ in 0..constants::MIC_CALIBRATION_WINDOWS {
+            wav.read(start, &mut window);
+            detector.calibrate(&window);
+            start += window.len();
+        }
+        let mut shots = 0;
+        while wav.read(start, &mut window) == window.len() {
+            let frames = start / constants::MIC_CHANNELS;
+            let tick = frames * 1000 / constants::MIC_SAMPLE_RATE as usize;
+            if detector.process(&window, tick) {
+                shots += 1;
+            }
+            start += window.len();
+        }
+        shots
+    }
+
+    #[test]
+    fn every_clap_is_one_shot() {
+        assert_eq!(shots(CLAPS), 3);
+    }
+
+    #[test]
+    fn room_noise_is_no_shot() {
+        assert_eq!(shots(NOISE), 0);
+    }
+}
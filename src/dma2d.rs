@@ -0,0 +1,121 @@
+use core::ptr;
+use stm32f7::board::rcc::Rcc;
+
+// register block of the Chrom-ART accelerator (RM0385, chapter 9)
+const DMA2D_BASE: u32 = 0x4002_B000;
+const CR: u32 = 0x00;
+const ISR: u32 = 0x04;
+const IFCR: u32 = 0x08;
+const FGMAR: u32 = 0x0C;
+const FGOR: u32 = 0x10;
+const FGPFCCR: u32 = 0x1C;
+const OPFCCR: u32 = 0x34;
+const OCOLR: u32 = 0x38;
+const OMAR: u32 = 0x3C;
+const OOR: u32 = 0x40;
+const NLR: u32 = 0x44;
+
+const CR_START: u32 = 1;
+const MODE_M2M: u32 = 0b00 << 16;
+const MODE_M2M_PFC: u32 = 0b01 << 16;
+const MODE_R2M: u32 = 0b11 << 16;
+
+const ISR_TEIF: u32 = 1 << 0;
+const ISR_TCIF: u32 = 1 << 1;
+
+const PFCCR_ALPHA_REPLACE: u32 = 0b01 << 16;
+
+#[derive(Clone, Copy)]
+pub enum PixelFormat {
+    Argb8888 = 0b000,
+    Rgb888 = 0b001,
+    Rgb565 = 0b010,
+    Argb1555 = 0b011,
+    Argb4444 = 0b100,
+}
+
+// both lcd layers are ARGB1555, so every transfer writes that format
+const OUTPUT_FORMAT: PixelFormat = PixelFormat::Argb1555;
+
+pub struct Dma2d();
+
+impl Dma2d {
+    pub fn init(rcc: &mut Rcc) -> Self {
+        rcc.ahb1enr.update(|r| r.set_dma2den(true));
+        Dma2d()
+    }
+
+    // fills a rectangle at `dst` with a ARGB1555 color; `dst_skip` is the
+    // number of pixels between the end of one line and the start of the next
+    pub fn fill(&mut self, dst: u32, dst_skip: u16, size: (u16, u16), color: u16) {
+        write_reg(OPFCCR, OUTPUT_FORMAT as u32);
+        write_reg(OCOLR, color as u32);
+        write_reg(OMAR, dst);
+        write_reg(OOR, dst_skip as u32);
+        write_reg(NLR, nlr(size));
+        self.start_and_wait(MODE_R2M);
+    }
+
+    // copies a ARGB1555 rectangle without conversion
+    pub fn copy(&mut self,
+                src: u32,
+                src_skip: u16,
+                dst: u32,
+                dst_skip: u16,
+                size: (u16, u16)) {
+        write_reg(FGMAR, src);
+        write_reg(FGOR, src_skip as u32);
+        write_reg(FGPFCCR, PixelFormat::Argb1555 as u32);
+        write_reg(OPFCCR, OUTPUT_FORMAT as u32);
+        write_reg(OMAR, dst);
+        write_reg(OOR, dst_skip as u32);
+        write_reg(NLR, nlr(size));
+        self.start_and_wait(MODE_M2M);
+    }
+
+    // copies a rectangle while converting it from `src_format` to ARGB1555
+    //
+    // `alpha` replaces the alpha channel of every source pixel if set.
+    pub fn convert(&mut self,
+                   src: u32,
+                   src_format: PixelFormat,
+                   src_skip: u16,
+                   alpha: Option<u8>,
+                   dst: u32,
+                   dst_skip: u16,
+                   size: (u16, u16)) {
+        let mut pfccr = src_format as u32;
+        if let Some(a) = alpha {
+            pfccr |= PFCCR_ALPHA_REPLACE | ((a as u32) << 24);
+        }
+
+        write_reg(FGMAR, src);
+        write_reg(FGOR, src_skip as u32);
+        write_reg(FGPFCCR, pfccr);
+        write_reg(OPFCCR, OUTPUT_FORMAT as u32);
+        write_reg(OMAR, dst);
+        write_reg(OOR, dst_skip as u32);
+        write_reg(NLR, nlr(size));
+        self.start_and_wait(MODE_M2M_PFC);
+    }
+
+    fn start_and_wait(&mut self, mode: u32) {
+        write_reg(CR, mode | CR_START);
+        while read_reg(ISR) & (ISR_TCIF | ISR_TEIF) == 0 {}
+        let isr = read_reg(ISR);
+        write_reg(IFCR, ISR_TCIF | ISR_TEIF);
+        assert!(isr & ISR_TEIF == 0, "dma2d transfer error");
+    }
+}
+
+fn nlr(size: (u16, u16)) -> u32 {
+    ((size.0 as u32) << 16) | (size.1 as u32)
+}
+
+fn write_reg(offset: u32, value: u32) {
+    unsafe { ptr::write_volatile((DMA2D_BASE + offset) as *mut u32, value) };
+}
+
+fn read_reg(offset: u32) -> u32 {
+    unsafe { ptr::read_volatile((DMA2D_BASE + offset) as *const u32) }
+}
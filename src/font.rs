@@ -0,0 +1,265 @@
+use collections::vec::Vec;
+use color::Color;
+
+// 5x7 glyphs for ascii 0x20 to 0x7E, one byte per column with the top row
+// in bit 0
+static GLYPHS_5X7: [u8; 95 * 5] = [
+    0x00, 0x00, 0x00, 0x00, 0x00, // ' '
+    0x00, 0x00, 0x5F, 0x00, 0x00, // '!'
+    0x00, 0x07, 0x00, 0x07, 0x00, // '"'
+    0x14, 0x7F, 0x14, 0x7F, 0x14, // '#'
+    0x24, 0x2A, 0x7F, 0x2A, 0x12, // '$'
+    0x23, 0x13, 0x08, 0x64, 0x62, // '%'
+    0x36, 0x49, 0x55, 0x22, 0x50, // '&'
+    0x00, 0x05, 0x03, 0x00, 0x00, // '''
+    0x00, 0x1C, 0x22, 0x41, 0x00, // '('
+    0x00, 0x41, 0x22, 0x1C, 0x00, // ')'
+    0x08, 0x2A, 0x1C, 0x2A, 0x08, // '*'
+    0x08, 0x08, 0x3E, 0x08, 0x08, // '+'
+    0x00, 0x50, 0x30, 0x00, 0x00, // ','
+    0x08, 0x08, 0x08, 0x08, 0x08, // '-'
+    0x00, 0x60, 0x60, 0x00, 0x00, // '.'
+    0x20, 0x10, 0x08, 0x04, 0x02, // '/'
+    0x3E, 0x51, 0x49, 0x45, 0x3E, // '0'
+    0x00, 0x42, 0x7F, 0x40, 0x00, // '1'
+    0x42, 0x61, 0x51, 0x49, 0x46, // '2'
+    0x21, 0x41, 0x45, 0x4B, 0x31, // '3'
+    0x18, 0x14, 0x12, 0x7F, 0x10, // '4'
+    0x27, 0x45, 0x45, 0x45, 0x39, // '5'
+    0x3C, 0x4A, 0x49, 0x49, 0x30, // '6'
+    0x01, 0x71, 0x09, 0x05, 0x03, // '7'
+    0x36, 0x49, 0x49, 0x49, 0x36, // '8'
+    0x06, 0x49, 0x49, 0x29, 0x1E, // '9'
+    0x00, 0x36, 0x36, 0x00, 0x00, // ':'
+    0x00, 0x56, 0x36, 0x00, 0x00, // ';'
+    0x00, 0x08, 0x14, 0x22, 0x41, // '<'
+    0x14, 0x14, 0x14, 0x14, 0x14, // '='
+    0x41, 0x22, 0x14, 0x08, 0x00, // '>'
+    0x02, 0x01, 0x51, 0x09, 0x06, // '?'
+    0x32, 0x49, 0x79, 0x41, 0x3E, // '@'
+    0x7E, 0x11, 0x11, 0x11, 0x7E, // 'A'
+    0x7F, 0x49, 0x49, 0x49, 0x36, // 'B'
+    0x3E, 0x41, 0x41, 0x41, 0x22, // 'C'
+    0x7F, 0x41, 0x41, 0x22, 0x1C, // 'D'
+    0x7F, 0x49, 0x49, 0x49, 0x41, // 'E'
+    0x7F, 0x09, 0x09, 0x01, 0x01, // 'F'
+    0x3E, 0x41, 0x41, 0x51, 0x32, // 'G'
+    0x7F, 0x08, 0x08, 0x08, 0x7F, // 'H'
+    0x00, 0x41, 0x7F, 0x41, 0x00, // 'I'
+    0x20, 0x40, 0x41, 0x3F, 0x01, // 'J'
+    0x7F, 0x08, 0x14, 0x22, 0x41, // 'K'
+    0x7F, 0x40, 0x40, 0x40, 0x40, // 'L'
+    0x7F, 0x02, 0x04, 0x02, 0x7F, // 'M'
+    0x7F, 0x04, 0x08, 0x10, 0x7F, // 'N'
+    0x3E, 0x41, 0x41, 0x41, 0x3E, // 'O'
+    0x7F, 0x09, 0x09, 0x09, 0x06, // 'P'
+    0x3E, 0x41, 0x51, 0x21, 0x5E, // 'Q'
+    0x7F, 0x09, 0x19, 0x29, 0x46, // 'R'
+    0x46, 0x49, 0x49, 0x49, 0x31, // 'S'
+    0x01, 0x01, 0x7F, 0x01, 0x01, // 'T'
+    0x3F, 0x40, 0x40, 0x40, 0x3F, // 'U'
+    0x1F, 0x20, 0x40, 0x20, 0x1F, // 'V'
+    0x7F, 0x20, 0x18, 0x20, 0x7F, // 'W'
+    0x63, 0x14, 0x08, 0x14, 0x63, // 'X'
+    0x03, 0x04, 0x78, 0x04, 0x03, // 'Y'
+    0x61, 0x51, 0x49, 0x45, 0x43, // 'Z'
+    0x00, 0x00, 0x7F, 0x41, 0x41, // '['
+    0x02, 0x04, 0x08, 0x10, 0x20, // '\'
+    0x41, 0x41, 0x7F, 0x00, 0x00, // ']'
+    0x04, 0x02, 0x01, 0x02, 0x04, // '^'
+    0x40, 0x40, 0x40, 0x40, 0x40, // '_'
+    0x00, 0x01, 0x02, 0x04, 0x00, // '`'
+    0x20, 0x54, 0x54, 0x54, 0x78, // 'a'
+    0x7F, 0x48, 0x44, 0x44, 0x38, // 'b'
+    0x38, 0x44, 0x44, 0x44, 0x20, // 'c'
+    0x38, 0x44, 0x44, 0x48, 0x7F, // 'd'
+    0x38, 0x54, 0x54, 0x54, 0x18, // 'e'
+    0x08, 0x7E, 0x09, 0x01, 0x02, // 'f'
+    0x08, 0x14, 0x54, 0x54, 0x3C, // 'g'
+    0x7F, 0x08, 0x04, 0x04, 0x78, // 'h'
+    0x00, 0x44, 0x7D, 0x40, 0x00, // 'i'
+    0x20, 0x40, 0x44, 0x3D, 0x00, // 'j'
+    0x00, 0x7F, 0x10, 0x28, 0x44, // 'k'
+    0x00, 0x41, 0x7F, 0x40, 0x00, // 'l'
+    0x7C, 0x04, 0x18, 0x04, 0x78, // 'm'
+    0x7C, 0x08, 0x04, 0x04, 0x78, // 'n'
+    0x38, 0x44, 0x44, 0x44, 0x38, // 'o'
+    0x7C, 0x14, 0x14, 0x14, 0x08, // 'p'
+    0x08, 0x14, 0x14, 0x18, 0x7C, // 'q'
+    0x7C, 0x08, 0x04, 0x04, 0x08, // 'r'
+    0x48, 0x54, 0x54, 0x54, 0x20, // 's'
+    0x04, 0x3F, 0x44, 0x40, 0x20, // 't'
+    0x3C, 0x40, 0x40, 0x20, 0x7C, // 'u'
+    0x1C, 0x20, 0x40, 0x20, 0x1C, // 'v'
+    0x3C, 0x40, 0x30, 0x40, 0x3C, // 'w'
+    0x44, 0x28, 0x10, 0x28, 0x44, // 'x'
+    0x0C, 0x50, 0x50, 0x50, 0x3C, // 'y'
+    0x44, 0x64, 0x54, 0x4C, 0x44, // 'z'
+    0x00, 0x08, 0x36, 0x41, 0x00, // '{'
+    0x00, 0x00, 0x7F, 0x00, 0x00, // '|'
+    0x00, 0x41, 0x36, 0x08, 0x00, // '}'
+    0x08, 0x04, 0x08, 0x10, 0x08, // '~'
+];
+
+pub struct Font {
+    glyphs: &'static [u8],
+    first_char: u8,
+    last_char: u8,
+    glyph_width: u16,
+    height: u16,
+    // proportional fonts drop empty columns left and right of a glyph
+    proportional: bool,
+    space_width: u16,
+    spacing: u16,
+}
+
+pub static FIXED_5X7: Font = Font {
+    glyphs: &GLYPHS_5X7,
+    first_char: 0x20,
+    last_char: 0x7E,
+    glyph_width: 5,
+    height: 7,
+    proportional: false,
+    space_width: 5,
+    spacing: 1,
+};
+
+pub static PROPORTIONAL_5X7: Font = Font {
+    glyphs: &GLYPHS_5X7,
+    first_char: 0x20,
+    last_char: 0x7E,
+    glyph_width: 5,
+    height: 7,
+    proportional: true,
+    space_width: 3,
+    spacing: 1,
+};
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Align {
+    Left,
+    Center,
+    Right,
+}
+
+#[derive(Clone, Copy)]
+pub struct TextStyle {
+    pub font: &'static Font,
+    pub color: Color,
+    // every font pixel becomes a square of scale x scale display pixels
+    pub scale: u16,
+    pub align: Align,
+    pub line_spacing: u16,
+}
+
+impl TextStyle {
+    pub fn new(font: &'static Font, color: Color) -> Self {
+        TextStyle {
+            font: font,
+            color: color,
+            scale: 1,
+            align: Align::Left,
+            line_spacing: 2,
+        }
+    }
+
+    pub fn with_scale(mut self, scale: u16) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    pub fn with_align(mut self, align: Align) -> Self {
+        self.align = align;
+        self
+    }
+
+    pub fn line_height(&self) -> u16 {
+        (self.font.height + self.line_spacing) * self.scale
+    }
+
+    pub fn text_width(&self, text: &str) -> u16 {
+        self.font.text_width(text) * self.scale
+    }
+}
+
+impl Font {
+    pub fn height(&self) -> u16 {
+        self.height
+    }
+
+    // columns of a glyph, characters without a glyph are shown as '?'
+    pub fn glyph(&self, c: char) -> &'static [u8] {
+        let code = c as u32;
+        let index = if code >= self.first_char as u32 && code <= self.last_char as u32 {
+            code - self.first_char as u32
+        } else {
+            '?' as u32 - self.first_char as u32
+        } as usize;
+        let width = self.glyph_width as usize;
+        let columns = &self.glyphs[index * width..(index + 1) * width];
+        if !self.proportional || c == ' ' {
+            return columns;
+        }
+        let start = columns.iter().position(|col| *col != 0).unwrap_or(0);
+        let end = columns.iter().rposition(|col| *col != 0).map_or(0, |i| i + 1);
+        &columns[start..end]
+    }
+
+    pub fn char_width(&self, c: char) -> u16 {
+        if c == ' ' {
+            self.space_width
+        } else {
+            self.glyph(c).len() as u16
+        }
+    }
+
+    // width of a single line of text in font pixels
+    pub fn text_width(&self, text: &str) -> u16 {
+        let mut width = 0;
+        for (i, c) in text.chars().enumerate() {
+            if i > 0 {
+                width += self.spacing;
+            }
+            width += self.char_width(c);
+        }
+        width
+    }
+
+    pub fn spacing(&self) -> u16 {
+        self.spacing
+    }
+}
+
+// splits text into lines that are at most `max_width` display pixels wide,
+// breaking at spaces if possible and always at '\n'
+pub fn wrap<'a>(text: &'a str, style: &TextStyle, max_width: u16) -> Vec<&'a str> {
+    let mut lines = Vec::new();
+    for paragraph in text.split('\n') {
+        let mut rest = paragraph;
+        while style.text_width(rest) > max_width {
+            let fit = fitting_prefix_len(rest, style, max_width);
+            // prefer the last space within the fitting part
+            let cut = match rest[..fit].rfind(' ') {
+                Some(space) if space > 0 => space,
+                _ => fit,
+            };
+            lines.push(rest[..cut].trim_right());
+            rest = rest[cut..].trim_left();
+        }
+        lines.push(rest);
+    }
+    lines
+}
+
+// byte length of the longest prefix that fits, at least one character
+fn fitting_prefix_len(text: &str, style: &TextStyle, max_width: u16) -> usize {
+    let mut fit = 0;
+    for (i, c) in text.char_indices() {
+        let end = i + c.len_utf8();
+        if fit > 0 && style.text_width(&text[..end]) > max_width {
+            break;
+        }
+        fit = end;
+    }
+    fit
+}
@@ -1,17 +1,50 @@
 use constants;
+use animation;
 use random;
 use renderer;
 use collections::vec::Vec;
-use seven_segment::SSDisplay;
-use stm32f7::board::sai::Sai;
+use collections::string::String;
+use seven_segment::{Align, SSDisplay};
+use font::{self, TextStyle};
+use level_meter::LevelMeter;
+use geometry::Rect;
+use color::Color;
+use transition::Effect;
+use microphone::Microphone;
+use speaker::Speaker;
+use mixer::Playback;
+use sound::Sample;
+use classifier::Sound;
+use score::{self, Score};
 use stm32f7::system_clock;
 
+// what a sound does to the targets under the touches
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Shot {
+    // claps and silent mode hit the touched targets once
+    Single,
+    // whistles also hit the targets around the touches
+    Blast,
+    // a sustained voice keeps firing as long as it lasts
+    Rapid,
+}
+
+impl Shot {
+    pub fn from_sound(sound: Sound) -> Self {
+        match sound {
+            Sound::Clap => Shot::Single,
+            Sound::Whistle => Shot::Blast,
+            Sound::Voice => Shot::Rapid,
+        }
+    }
+}
+
 pub struct Game {
     evil_targets: Vec<Target>,
     hero_targets: Vec<Target>,
     rend: renderer::Renderer,
-    score: u16,
-    highscore: u16,
+    score: Score,
+    highscore: Score,
     countdown: u16,
     rand: random::MTRng32,
     tick: usize,
@@ -24,10 +57,34 @@ pub struct Game {
     super_target_img: &'static [u8],
     evil_target_img: &'static [u8],
     silent_mode: bool,
+    last_silent_toggle: usize,
+    level_meter: LevelMeter,
+    last_rapid_shot: usize,
+    speaker: Speaker,
+    music: Option<Playback>,
+    last_sound_toggle: usize,
 }
 
 impl Game {
-    pub fn new(rend: renderer::Renderer, rand: random::MTRng32) -> Self {
+    pub fn new(mut rend: renderer::Renderer, rand: random::MTRng32, mut speaker: Speaker) -> Self {
+        // score, timer and silent button are drawn directly, keep the scene
+        // away from them
+        let score_width = Self::hud_width(constants::SCORE_DIGITS);
+        let countdown_width = Self::hud_width(constants::COUNTDOWN_DIGITS);
+        let hud_height = SSDisplay::calculate_height(constants::ELEMENT_WIDTH_SMALL);
+        rend.reserve(Rect::new(0, 0, score_width, hud_height));
+        rend.reserve(Rect::new((constants::DISPLAY_SIZE.0 - countdown_width) as i32,
+                               0,
+                               countdown_width,
+                               hud_height));
+        rend.reserve(Rect::new(0,
+                               (constants::DISPLAY_SIZE.1 - constants::SILENT_BTN_SIZE.1) as i32,
+                               constants::SILENT_BTN_SIZE.0,
+                               constants::SILENT_BTN_SIZE.1));
+        rend.reserve(Self::level_meter_rect());
+
+        speaker.mixer().set_muted(!constants::SOUND_ENABLED);
+
         let tick = system_clock::ticks();
         Game {
             evil_targets: Vec::new(),
@@ -41,52 +98,137 @@ impl Game {
             last_super_target_render_time: tick,
             super_target_hiding_duration: 0,
             last_ssd_render_time: tick,
-            ss_ctr_display:
-                SSDisplay::new((constants::DISPLAY_SIZE.0 -
-                                SSDisplay::calculate_width(constants::ELEMENT_WIDTH_SMALL,
-                                                           constants::ELEMENT_GAP_SMALL),
-                                0),
-                               constants::ELEMENT_WIDTH_SMALL,
-                               constants::ELEMENT_GAP_SMALL),
+            ss_ctr_display: SSDisplay::new((constants::DISPLAY_SIZE.0 - countdown_width, 0),
+                                           constants::COUNTDOWN_DIGITS,
+                                           constants::ELEMENT_WIDTH_SMALL,
+                                           constants::ELEMENT_GAP_SMALL)
+                .without_leading_zeros(Align::Right),
             ss_hs_display: SSDisplay::new((0, 0),
+                                          constants::SCORE_DIGITS,
                                           constants::ELEMENT_WIDTH_SMALL,
                                           constants::ELEMENT_GAP_SMALL),
             hero_target_img: constants::MEXICAN,
             super_target_img: constants::SUPER_TRUMP,
             evil_target_img: constants::TRUMP,
             silent_mode: false,
+            last_silent_toggle: tick,
+            level_meter: LevelMeter::new(Self::level_meter_rect()),
+            last_rapid_shot: tick,
+            speaker: speaker,
+            music: None,
+            last_sound_toggle: tick,
         }
     }
 
+    // advances animations and makes everything drawn since the last call
+    // visible
+    pub fn present(&mut self) {
+        self.rend.animate(system_clock::ticks());
+        self.rend.present();
+        self.speaker.update();
+    }
+
+    fn play(&mut self, sample: &'static Sample) {
+        self.speaker
+            .mixer()
+            .play(sample, constants::EFFECT_VOLUME, false);
+    }
+
     pub fn draw_game_banner(&mut self) {
-        let start_y = constants::GAME_OVER_OFFSET_Y - 20;
+        let start_y = constants::GAME_OVER_OFFSET_Y as i32 - 20;
         self.rend
             .draw_dump(0, start_y, constants::FIESTA_SIZE, constants::FIESTA_PINATA);
     }
 
     pub fn draw_start_banner(&mut self) {
+        self.rend.start_transition(Effect::Wipe, system_clock::ticks());
         self.clear_banner();
         // draw start banner
-        let start_y = constants::GAME_OVER_OFFSET_Y - 20;
+        let start_y = constants::GAME_OVER_OFFSET_Y as i32 - 20;
         self.rend
             .draw_dump(0, start_y, constants::START_SIZE, constants::START);
         //draw game mode banner
-        let offset_x = constants::DISPLAY_SIZE.0 / 4 - constants::GAME_MODE_BTN_SIZE.0 / 2;
+        let offset_x = (constants::DISPLAY_SIZE.0 / 4 - constants::GAME_MODE_BTN_SIZE.0 / 2) as i32;
         self.rend
             .draw_dump(offset_x,
-                       start_y + constants::START_SIZE.1 + 20,
+                       start_y + constants::START_SIZE.1 as i32 + 20,
                        constants::GAME_MODE_BTN_SIZE,
                        constants::BURGER_MODE);
         self.rend
-            .draw_dump(offset_x + constants::DISPLAY_SIZE.0 / 2,
-                       start_y + constants::START_SIZE.1 + 20,
+            .draw_dump(offset_x + constants::DISPLAY_SIZE.0 as i32 / 2,
+                       start_y + constants::START_SIZE.1 as i32 + 20,
                        constants::GAME_MODE_BTN_SIZE,
                        constants::TACO_MODE);
+        self.draw_sound_setting();
     }
 
-    pub fn start(&mut self, touch: (u16, u16)) {
+    pub fn sound_setting_touched(touch: (u16, u16)) -> bool {
+        Self::sound_setting_rect().contains(touch.0 as i32, touch.1 as i32)
+    }
+
+    // steps through the volumes and off, the beep tells how loud it is now
+    pub fn toggle_sound(&mut self) {
+        let tick = system_clock::ticks();
+        if tick.wrapping_sub(self.last_sound_toggle) < constants::BUTTON_DEBOUNCE {
+            return;
+        }
+        self.last_sound_toggle = tick;
+        {
+            let mixer = self.speaker.mixer();
+            let volume = mixer.volume();
+            if mixer.is_muted() {
+                mixer.set_volume(constants::SOUND_VOLUMES[0]);
+                mixer.set_muted(false);
+            } else {
+                match constants::SOUND_VOLUMES.iter().position(|v| *v > volume) {
+                    Some(i) => mixer.set_volume(constants::SOUND_VOLUMES[i]),
+                    None => mixer.set_muted(true),
+                }
+            }
+        }
+        self.draw_sound_setting();
+        self.play(&constants::BEEP_SOUND);
+    }
+
+    fn draw_sound_setting(&mut self) {
+        let text = {
+            let mixer = self.speaker.mixer();
+            Self::sound_setting_text(mixer.is_muted(), mixer.volume())
+        };
+        let style = TextStyle::new(&font::PROPORTIONAL_5X7, constants::BLACK)
+            .with_scale(2)
+            .with_align(font::Align::Right);
+        let rect = Self::sound_setting_rect();
+        let text_height = (style.font.height() * style.scale) as i32;
+        self.rend.clear_rect(rect);
+        self.rend.draw_text(renderer::Layer::Foreground,
+                            &text,
+                            (rect.right() - 6, rect.y + (rect.height as i32 - text_height) / 2),
+                            &style);
+    }
+
+    // "SOUND OFF" or a + per volume step
+    fn sound_setting_text(muted: bool, volume: u8) -> String {
+        let mut text = String::from("SOUND ");
+        if muted {
+            text.push_str("OFF");
+        } else {
+            for v in &constants::SOUND_VOLUMES {
+                if *v <= volume {
+                    text.push('+');
+                }
+            }
+        }
+        text
+    }
+
+    pub fn start(&mut self, touch: (u16, u16), mic: &mut Microphone) {
+        self.rend.start_transition(Effect::Slide, system_clock::ticks());
         self.ss_ctr_display
-            .render(constants::GAME_TIME, constants::BLACK, &mut self.rend);
+            .render_time(constants::GAME_TIME as u32 * 1000,
+                         constants::COUNTDOWN_TENTHS_BELOW,
+                         constants::BLACK,
+                         &mut self.rend);
         self.ss_hs_display
             .render(0, constants::BLACK, &mut self.rend);
         let tick = system_clock::ticks();
@@ -98,6 +240,8 @@ impl Game {
                                    constants::SUPER_TARGET_HIDING_DURATION.1);
         self.countdown = constants::GAME_TIME;
         self.clear_banner();
+        // nobody listened since the calibration or the last game
+        mic.skip_to_latest();
 
         // which game mode?
         if touch.0 > constants::DISPLAY_SIZE.0 / 2 {
@@ -110,6 +254,12 @@ impl Game {
             self.evil_target_img = constants::MEXICAN;
         }
         self.draw_silent_button();
+        if let Some(music) = self.music {
+            self.speaker.mixer().stop(music);
+        }
+        self.music = Some(self.speaker
+                              .mixer()
+                              .play(&constants::MARIACHI_MUSIC, constants::MUSIC_VOLUME, true));
     }
 
     fn draw_silent_button(&mut self) {
@@ -120,7 +270,7 @@ impl Game {
         };
         self.rend
             .draw_dump(0,
-                       constants::DISPLAY_SIZE.1 - constants::SILENT_BTN_SIZE.1,
+                       (constants::DISPLAY_SIZE.1 - constants::SILENT_BTN_SIZE.1) as i32,
                        constants::SILENT_BTN_SIZE,
                        silent_btn);
     }
@@ -136,52 +286,69 @@ impl Game {
         self.tick = system_clock::ticks();
         if self.tick - self.last_ssd_render_time >= 1000 {
             self.countdown -= if self.countdown > 0 { 1 } else { 0 };
-            let color = if self.countdown <= 5 {
-                constants::RED
-            } else {
-                constants::BLACK
-            };
-            self.ss_ctr_display
-                .render(self.countdown, color, &mut self.rend);
             self.last_ssd_render_time = self.tick;
+            if self.countdown > 0 && self.countdown < constants::COUNTDOWN_BEEPS_BELOW {
+                self.play(&constants::BEEP_SOUND);
+            }
         }
+        let color = if self.countdown <= 5 {
+            constants::RED
+        } else {
+            constants::BLACK
+        };
+        // the display only redraws segments that changed, so this is cheap
+        let elapsed = (self.tick - self.last_ssd_render_time) as u32;
+        let remaining = (self.countdown as u32 * 1000).saturating_sub(elapsed);
+        self.ss_ctr_display
+            .render_time(remaining,
+                         constants::COUNTDOWN_TENTHS_BELOW,
+                         color,
+                         &mut self.rend);
         self.countdown
     }
 
     pub fn draw_missing_targets(&mut self) {
         // rendering random positioned evil targets
         while self.evil_targets.len() < constants::MAX_EVIL_TARGETS {
-            let lifetime = Self::get_rnd_lifetime(&mut self.rand, 3000, 5000);
             let pos: (u16, u16) =
                 Self::get_rnd_pos(&mut self.rand, &self.hero_targets, &self.evil_targets);
-            let evil_target = Target::new(pos.0,
-                                          pos.1,
-                                          constants::TARGET_SIZE.0,
-                                          constants::TARGET_SIZE.1,
-                                          constants::EVIL_POINTS,
-                                          self.tick,
-                                          lifetime);
-            let super_evil_target = Target::new(pos.0,
-                                                pos.1,
-                                                constants::TARGET_SIZE.0,
-                                                constants::TARGET_SIZE.1,
-                                                constants::SUPER_EVIL_POINTS,
-                                                self.tick,
-                                                2000);
-            if self.tick - self.last_super_target_render_time >= self.super_target_hiding_duration {
-                self.rend
-                    .draw_dump(pos.0, pos.1, constants::TARGET_SIZE, self.super_target_img);
+            let evil_target = if self.tick - self.last_super_target_render_time >=
+                                 self.super_target_hiding_duration {
                 self.last_super_target_render_time = self.tick;
-                self.evil_targets.push(super_evil_target);
                 self.super_target_hiding_duration =
                     Self::get_rnd_lifetime(&mut self.rand,
                                            constants::SUPER_TARGET_HIDING_DURATION.0,
                                            constants::SUPER_TARGET_HIDING_DURATION.1);
+                let sprite = self.rend.add_sprite(pos.0 as i32,
+                                                  pos.1 as i32,
+                                                  constants::TARGET_SIZE,
+                                                  self.super_target_img);
+                self.rend.animate_sprite(sprite, &animation::POP_IN, self.tick);
+                Target::new(pos.0,
+                            pos.1,
+                            constants::TARGET_SIZE.0,
+                            constants::TARGET_SIZE.1,
+                            constants::SUPER_EVIL_POINTS,
+                            self.tick,
+                            2000,
+                            sprite)
             } else {
-                self.rend
-                    .draw_dump(pos.0, pos.1, constants::TARGET_SIZE, self.evil_target_img);
-                self.evil_targets.push(evil_target);
-            }
+                let lifetime = Self::get_rnd_lifetime(&mut self.rand, 3000, 5000);
+                let sprite = self.rend.add_sprite(pos.0 as i32,
+                                                  pos.1 as i32,
+                                                  constants::TARGET_SIZE,
+                                                  self.evil_target_img);
+                self.rend.animate_sprite(sprite, &animation::POP_IN, self.tick);
+                Target::new(pos.0,
+                            pos.1,
+                            constants::TARGET_SIZE.0,
+                            constants::TARGET_SIZE.1,
+                            constants::EVIL_POINTS,
+                            self.tick,
+                            lifetime,
+                            sprite)
+            };
+            self.evil_targets.push(evil_target);
         }
 
         // rendering random positioned hero targets
@@ -189,50 +356,124 @@ impl Game {
             let lifetime = Self::get_rnd_lifetime(&mut self.rand, 3000, 5000);
             let pos: (u16, u16) =
                 Self::get_rnd_pos(&mut self.rand, &self.hero_targets, &self.evil_targets);
+            let sprite = self.rend.add_sprite(pos.0 as i32,
+                                              pos.1 as i32,
+                                              constants::TARGET_SIZE,
+                                              self.hero_target_img);
+            self.rend.animate_sprite(sprite, &animation::POP_IN, self.tick);
             let hero_target = Target::new(pos.0,
                                           pos.1,
                                           constants::TARGET_SIZE.0,
                                           constants::TARGET_SIZE.1,
                                           constants::HERO_POINTS,
                                           self.tick,
-                                          lifetime);
-            self.rend
-                .draw_dump(pos.0, pos.1, constants::TARGET_SIZE, self.hero_target_img);
+                                          lifetime,
+                                          sprite);
             self.hero_targets.push(hero_target);
         }
     }
 
-    pub fn process_shooting(&mut self, sai_2: &'static Sai, touches: Vec<(u16, u16)>) {
-        if Self::silent_btn_touched(&touches) {
+    pub fn process_shooting(&mut self, mic: &mut Microphone, touches: Vec<(u16, u16)>) {
+        // a touch lasts several frames, ignore the button for a moment after it toggled
+        if Self::silent_btn_touched(&touches) &&
+           self.tick.wrapping_sub(self.last_silent_toggle) >= constants::BUTTON_DEBOUNCE {
+            self.last_silent_toggle = self.tick;
             self.silent_mode = !self.silent_mode;
             self.draw_silent_button();
-            ::stm32f7::system_clock::wait(250);
-        }
-        if !self.silent_mode && !Self::vol_limit_reached(sai_2) {
-            return;
+            if self.silent_mode {
+                self.level_meter.hide(&mut self.rend);
+            } else {
+                // the ring filled up with old sounds meanwhile
+                mic.skip_to_latest();
+            }
         }
-        let mut hit_evil_targets = Target::check_for_hit(&mut self.evil_targets, &touches);
+        let shot = if self.silent_mode {
+            Shot::Single
+        } else {
+            let sound = mic.poll(self.tick);
+            self.level_meter.render(mic.level(), mic.threshold(), &mut self.rend);
+            match sound {
+                Some(sound) => Shot::from_sound(sound),
+                None => return,
+            }
+        };
+        let radius = match shot {
+            Shot::Single => 0,
+            Shot::Blast => constants::BLAST_RADIUS,
+            Shot::Rapid => {
+                if self.tick.wrapping_sub(self.last_rapid_shot) < constants::RAPID_FIRE_INTERVAL {
+                    return;
+                }
+                self.last_rapid_shot = self.tick;
+                0
+            }
+        };
+        let mut hit_evil_targets =
+            Target::check_for_hit(&mut self.evil_targets, &touches, radius);
         hit_evil_targets.sort();
         for hit_index in hit_evil_targets.iter().rev() {
             let t = self.evil_targets.remove(*hit_index);
-            self.rend.clear(t.x, t.y, (t.width, t.height));
-            self.score += t.bounty;
+            self.rend.remove_sprite_animated(t.sprite, &animation::EXPLODE, self.tick);
+            self.burst(&t);
+            self.popup(&t, t.bounty, constants::GREEN);
+            self.add_points(t.bounty);
             self.ss_hs_display
-                .render(self.score, constants::GREEN, &mut self.rend);
+                .render_signed(self.score, constants::GREEN, &mut self.rend);
         }
-        let mut hit_hero_targets = Target::check_for_hit(&mut self.hero_targets, &touches);
+        let mut hit_hero_targets =
+            Target::check_for_hit(&mut self.hero_targets, &touches, radius);
         hit_hero_targets.sort();
         for hit_index in hit_hero_targets.iter().rev() {
             let t = self.hero_targets.remove(*hit_index);
-            self.rend.clear(t.x, t.y, (t.width, t.height));
-            self.score -= if self.score < t.bounty {
-                self.score
-            } else {
-                t.bounty
-            };
+            self.rend.remove_sprite_animated(t.sprite, &animation::EXPLODE, self.tick);
+            self.burst(&t);
+            self.popup(&t, -t.bounty, constants::RED);
+            self.add_points(-t.bounty);
             self.ss_hs_display
-                .render(self.score, constants::RED, &mut self.rend);
+                .render_signed(self.score, constants::RED, &mut self.rend);
+        }
+        // one sound per shot, however many targets it hit
+        if !hit_hero_targets.is_empty() || (hit_evil_targets.is_empty() && !touches.is_empty()) {
+            self.play(&constants::MISS_SOUND);
+        } else if !hit_evil_targets.is_empty() {
+            self.play(&constants::HIT_SOUND);
+        }
+    }
+
+    fn add_points(&mut self, points: Score) {
+        self.score = score::add(self.score, points, constants::SCORE_POLICY);
+    }
+
+    // confetti and candy flying out of a hit target
+    fn burst(&mut self, target: &Target) {
+        let center = ((target.x + target.width / 2) as i32, (target.y + target.height / 2) as i32);
+        self.rend
+            .emit_particles(center, constants::BURST_PARTICLES, self.tick, &mut self.rand);
+    }
+
+    // floating "+50" or "-70" where the target was hit
+    fn popup(&mut self, target: &Target, points: Score, color: Color) {
+        let pos = ((target.x + target.width / 2) as i32, target.y as i32);
+        let text = Self::points_text(points);
+        self.rend.show_popup(&text, pos, color, self.tick);
+    }
+
+    fn points_text(points: Score) -> String {
+        let mut digits = Vec::new();
+        let mut rest = (points as i64).abs();
+        loop {
+            digits.push((b'0' + (rest % 10) as u8) as char);
+            rest /= 10;
+            if rest == 0 {
+                break;
+            }
+        }
+        let mut text = String::new();
+        text.push(if points < 0 { '-' } else { '+' });
+        for d in digits.iter().rev() {
+            text.push(*d);
         }
+        text
     }
 
     pub fn purge_old_targets(&mut self) {
@@ -243,20 +484,16 @@ impl Game {
             for i in (0..target_vec.len()).rev() {
                 if self.tick - target_vec[i].birthday > target_vec[i].lifetime {
                     let t = target_vec.remove(i);
-                    self.rend.clear(t.x, t.y, (t.width, t.height));
+                    self.rend.remove_sprite_animated(t.sprite, &animation::POP_OUT, self.tick);
                 }
             }
         }
     }
 
     pub fn reset_game(&mut self) {
-        for t in &self.evil_targets {
-            self.rend.clear(t.x, t.y, (t.width, t.height));
-        }
-
-        for t in &self.hero_targets {
-            self.rend.clear(t.x, t.y, (t.width, t.height));
-        }
+        // erase the targets now, before anything is drawn on top of them
+        self.rend.clear_scene();
+        self.rend.compose();
 
         self.evil_targets = Vec::new();
         self.hero_targets = Vec::new();
@@ -270,53 +507,47 @@ impl Game {
         }
 
         let score = self.score;
+        if let Some(music) = self.music.take() {
+            self.speaker.mixer().stop(music);
+        }
+        self.play(&constants::GAME_OVER_SOUND);
+        self.rend.start_transition(Effect::Fade, system_clock::ticks());
         self.reset_game();
         self.rend
             .clear(0,
                    constants::DISPLAY_SIZE.1 - constants::SILENT_BTN_SIZE.1 - 1,
                    constants::SILENT_BTN_SIZE);
+        self.level_meter.hide(&mut self.rend);
         self.rend
             .draw_dump(0,
-                       constants::GAME_OVER_OFFSET_Y,
+                       constants::GAME_OVER_OFFSET_Y as i32,
                        constants::GAME_OVER_SIZE,
                        constants::GAMEOVER);
 
-        let ss_width = SSDisplay::calculate_width(constants::ELEMENT_WIDTH_BIG,
+        let ss_width = SSDisplay::calculate_width(constants::SCORE_DIGITS,
+                                                  constants::ELEMENT_WIDTH_BIG,
                                                   constants::ELEMENT_GAP_BIG);
         let ss_x = (constants::DISPLAY_SIZE.0 - ss_width) / 2;
         let ss_y = constants::GAME_OVER_OFFSET_Y + constants::GAME_OVER_SIZE.1 + 6;
-        let ss_end_display = SSDisplay::new((ss_x, ss_y),
-                                            constants::ELEMENT_WIDTH_BIG,
-                                            constants::ELEMENT_GAP_BIG);
-        ss_end_display.render(score, constants::BLACK, &mut self.rend);
+        let mut ss_end_display = SSDisplay::new((ss_x, ss_y),
+                                                constants::SCORE_DIGITS,
+                                                constants::ELEMENT_WIDTH_BIG,
+                                                constants::ELEMENT_GAP_BIG);
+        ss_end_display.render_signed(score, constants::BLACK, &mut self.rend);
 
         let hs_x = ((constants::DISPLAY_SIZE.0 - ss_width) / 2) -
                    SSDisplay::calculate_hs_prefix_width(constants::ELEMENT_WIDTH_BIG,
                                                         constants::ELEMENT_GAP_BIG);
         let hs_y = ss_y + SSDisplay::calculate_height(constants::ELEMENT_WIDTH_BIG) + 6;
-        let hs_end_display = SSDisplay::new((hs_x, hs_y),
-                                            constants::ELEMENT_WIDTH_BIG,
-                                            constants::ELEMENT_GAP_BIG);
-        hs_end_display.render_hs(self.highscore, constants::BLACK, &mut self.rend);
+        let mut hs_end_display = SSDisplay::new((hs_x, hs_y),
+                                                constants::SCORE_DIGITS,
+                                                constants::ELEMENT_WIDTH_BIG,
+                                                constants::ELEMENT_GAP_BIG);
+        // never below 0, it starts there and only ever grows
+        hs_end_display.render_hs(self.highscore as u32, constants::BLACK, &mut self.rend);
     }
 
 
-    fn vol_limit_reached(sai_2: &'static Sai) -> bool {
-        while !sai_2.bsr.read().freq() {} // fifo_request_flag
-        let data0 = sai_2.bdr.read().data() as i16 as i32;
-        while !sai_2.bsr.read().freq() {} // fifo_request_flag
-        let data1 = sai_2.bdr.read().data() as i16 as i32;
-
-        let mic_data = if data0.abs() > data1.abs() {
-            data0.abs() as u16
-        } else {
-            data1.abs() as u16
-        };
-
-        // mic_data reprents our "volume". Magic number after testing.
-        mic_data > constants::VOLUME_THRESHOLD
-    }
-
     fn silent_btn_touched(touches: &[(u16, u16)]) -> bool {
         let silent_ul = (0, constants::DISPLAY_SIZE.1 - constants::SILENT_BTN_SIZE.1);
         let silent_lr = (constants::SILENT_BTN_SIZE.0, constants::DISPLAY_SIZE.1);
@@ -328,6 +559,28 @@ impl Game {
         false
     }
 
+    fn hud_width(digits: usize) -> u16 {
+        SSDisplay::calculate_width(digits,
+                                   constants::ELEMENT_WIDTH_SMALL,
+                                   constants::ELEMENT_GAP_SMALL)
+    }
+
+    // bottom right corner of the start screen, below the game mode buttons
+    fn sound_setting_rect() -> Rect {
+        Rect::new((constants::DISPLAY_SIZE.0 - constants::SOUND_SETTING_SIZE.0) as i32,
+                  (constants::DISPLAY_SIZE.1 - constants::SOUND_SETTING_SIZE.1) as i32,
+                  constants::SOUND_SETTING_SIZE.0,
+                  constants::SOUND_SETTING_SIZE.1)
+    }
+
+    // right of the silent button, aligned to the bottom of the display
+    fn level_meter_rect() -> Rect {
+        Rect::new((constants::SILENT_BTN_SIZE.0 + constants::LEVEL_METER_GAP) as i32,
+                  (constants::DISPLAY_SIZE.1 - constants::LEVEL_METER_SIZE.1) as i32,
+                  constants::LEVEL_METER_SIZE.0,
+                  constants::LEVEL_METER_SIZE.1)
+    }
+
     fn get_rnd_lifetime(rnd: &mut random::MTRng32, min: usize, max: usize) -> usize {
         let range = max - min;
         min + ((rnd.rand() as usize) % range)
@@ -369,18 +622,13 @@ impl Game {
     }
 
     fn pos_is_okay(pos: (u16, u16), existing_hero: &[Target], existing_evil: &[Target]) -> bool {
-        // check score, timer and silent button
+        // check score, timer, silent button and level meter
         let score_ul = (0, 0);
-        let score_lr = (SSDisplay::calculate_width(constants::ELEMENT_WIDTH_SMALL,
-                                                   constants::ELEMENT_GAP_SMALL),
+        let score_lr = (Self::hud_width(constants::SCORE_DIGITS),
                         SSDisplay::calculate_height(constants::ELEMENT_WIDTH_SMALL));
-        let timer_ul = (constants::DISPLAY_SIZE.0 -
-                        SSDisplay::calculate_width(constants::ELEMENT_WIDTH_SMALL,
-                                                   constants::ELEMENT_GAP_SMALL),
+        let timer_ul = (constants::DISPLAY_SIZE.0 - Self::hud_width(constants::COUNTDOWN_DIGITS),
                         0);
-        let timer_lr = (timer_ul.0 +
-                        SSDisplay::calculate_width(constants::ELEMENT_WIDTH_SMALL,
-                                                   constants::ELEMENT_GAP_SMALL),
+        let timer_lr = (constants::DISPLAY_SIZE.0,
                         SSDisplay::calculate_height(constants::ELEMENT_WIDTH_SMALL));
         let silent_ul = (0, constants::DISPLAY_SIZE.1 - constants::SILENT_BTN_SIZE.1);
         let silent_lr = (constants::SILENT_BTN_SIZE.0, constants::DISPLAY_SIZE.1);
@@ -393,6 +641,13 @@ impl Game {
             silent_offset_pos < constants::DISPLAY_SIZE.1) {
             return false;
         }
+        let target_rect = Rect::new(pos.0 as i32,
+                                    pos.1 as i32,
+                                    constants::TARGET_SIZE.0,
+                                    constants::TARGET_SIZE.1);
+        if target_rect.overlaps(&Self::level_meter_rect()) {
+            return false;
+        }
 
         for hero in existing_hero {
             if Self::are_overlapping_targets(hero, pos) {
@@ -413,9 +668,11 @@ pub struct Target {
     pub y: u16,
     pub width: u16,
     pub height: u16,
-    pub bounty: u16,
+    // positive, hero targets subtract it
+    pub bounty: Score,
     pub birthday: usize,
     pub lifetime: usize,
+    pub sprite: renderer::SpriteId,
 }
 
 impl Target {
@@ -423,9 +680,10 @@ impl Target {
                y: u16,
                width: u16,
                height: u16,
-               bounty: u16,
+               bounty: Score,
                birthday: usize,
-               lifetime: usize)
+               lifetime: usize,
+               sprite: renderer::SpriteId)
                -> Self {
         Target {
             x: x,
@@ -435,20 +693,27 @@ impl Target {
             bounty: bounty,
             birthday: birthday,
             lifetime: lifetime,
+            sprite: sprite,
         }
     }
 
-    fn coord_is_inside(&mut self, x: u16, y: u16) -> bool {
-        x >= self.x && x < self.x + self.width && y >= self.y && y < self.y + self.height
+    // true if the coordinate lies in the target grown by `radius` on all sides
+    fn coord_is_inside(&mut self, x: u16, y: u16, radius: u16) -> bool {
+        let (x, y) = (x as i32, y as i32);
+        let r = radius as i32;
+        x >= self.x as i32 - r && x < (self.x + self.width) as i32 + r &&
+        y >= self.y as i32 - r && y < (self.y + self.height) as i32 + r
     }
 
-    pub fn check_for_hit(targets: &mut [Target], touches: &[(u16, u16)]) -> Vec<usize> {
+    // every target is reported once, no matter how many touches hit it
+    pub fn check_for_hit(targets: &mut [Target],
+                         touches: &[(u16, u16)],
+                         radius: u16)
+                         -> Vec<usize> {
         let mut indices: Vec<usize> = Vec::new();
         for (i, target) in targets.iter_mut().enumerate() {
-            for touch in touches {
-                if target.coord_is_inside(touch.0, touch.1) {
-                    indices.push(i);
-                }
+            if touches.iter().any(|t| target.coord_is_inside(t.0, t.1, radius)) {
+                indices.push(i);
             }
         }
         indices
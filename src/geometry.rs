@@ -0,0 +1,102 @@
+use core::cmp;
+use collections::vec::Vec;
+
+// positions are signed, so rectangles may lie partially or completely
+// outside of the display
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Rect {
+    pub x: i32,
+    pub y: i32,
+    pub width: u16,
+    pub height: u16,
+}
+
+impl Rect {
+    pub fn new(x: i32, y: i32, width: u16, height: u16) -> Self {
+        Rect {
+            x: x,
+            y: y,
+            width: width,
+            height: height,
+        }
+    }
+
+    // x coordinate of the first column right of the rectangle
+    pub fn right(&self) -> i32 {
+        self.x + self.width as i32
+    }
+
+    // y coordinate of the first row below the rectangle
+    pub fn bottom(&self) -> i32 {
+        self.y + self.height as i32
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.width == 0 || self.height == 0
+    }
+
+    pub fn contains(&self, x: i32, y: i32) -> bool {
+        x >= self.x && x < self.right() && y >= self.y && y < self.bottom()
+    }
+
+    pub fn contains_rect(&self, other: &Rect) -> bool {
+        other.x >= self.x && other.y >= self.y && other.right() <= self.right() &&
+        other.bottom() <= self.bottom()
+    }
+
+    pub fn overlaps(&self, other: &Rect) -> bool {
+        self.x < other.right() && other.x < self.right() && self.y < other.bottom() &&
+        other.y < self.bottom()
+    }
+
+    pub fn intersection(&self, other: &Rect) -> Option<Rect> {
+        if !self.overlaps(other) {
+            return None;
+        }
+        let x = cmp::max(self.x, other.x);
+        let y = cmp::max(self.y, other.y);
+        Some(Rect::new(x,
+                       y,
+                       (cmp::min(self.right(), other.right()) - x) as u16,
+                       (cmp::min(self.bottom(), other.bottom()) - y) as u16))
+    }
+
+    // smallest rectangle containing both rectangles
+    pub fn union(&self, other: &Rect) -> Rect {
+        let x = cmp::min(self.x, other.x);
+        let y = cmp::min(self.y, other.y);
+        Rect::new(x,
+                  y,
+                  (cmp::max(self.right(), other.right()) - x) as u16,
+                  (cmp::max(self.bottom(), other.bottom()) - y) as u16)
+    }
+
+    // parts of self that are not covered by `other`, at most four rectangles
+    pub fn subtract(&self, other: &Rect) -> Vec<Rect> {
+        let mut parts = Vec::new();
+        let cut = match self.intersection(other) {
+            Some(cut) => cut,
+            None => {
+                parts.push(*self);
+                return parts;
+            }
+        };
+        // full width stripes above and below, side parts next to the cut
+        let above = Rect::new(self.x, self.y, self.width, (cut.y - self.y) as u16);
+        let below = Rect::new(self.x,
+                              cut.bottom(),
+                              self.width,
+                              (self.bottom() - cut.bottom()) as u16);
+        let left = Rect::new(self.x, cut.y, (cut.x - self.x) as u16, cut.height);
+        let right = Rect::new(cut.right(),
+                              cut.y,
+                              (self.right() - cut.right()) as u16,
+                              cut.height);
+        for part in &[above, below, left, right] {
+            if !part.is_empty() {
+                parts.push(*part);
+            }
+        }
+        parts
+    }
+}
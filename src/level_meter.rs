@@ -0,0 +1,72 @@
+// vertical bar showing how loud the microphone hears the player
+//
+// the trigger threshold sits in the middle of the bar, so anything that
+// reaches the upper half is loud enough for a shot. only the part of the
+// bar that changed since the last render is drawn.
+use core::cmp;
+use renderer::{Layer, Renderer};
+use geometry::Rect;
+use color::{self, Color};
+
+pub struct LevelMeter {
+    rect: Rect,
+    // filled height and color of the bar on screen, none if hidden
+    shown: Option<(u16, Color)>,
+}
+
+impl LevelMeter {
+    pub fn new(rect: Rect) -> Self {
+        LevelMeter {
+            rect: rect,
+            shown: None,
+        }
+    }
+
+    pub fn render(&mut self, level: u16, threshold: u16, rend: &mut Renderer) {
+        let height = self.rect.height;
+        let full_scale = cmp::max(2 * threshold as u32, 1);
+        let filled = cmp::min(level as u32 * height as u32 / full_scale, height as u32) as u16;
+        let color = if level >= threshold {
+            color::RED
+        } else {
+            color::GREEN
+        };
+        let old_filled = match self.shown {
+            Some((f, c)) if c == color => f,
+            Some(_) => {
+                // the whole bar changes color
+                rend.fill_rect(Layer::Foreground, self.rect, color::TRANSPARENT);
+                0
+            }
+            None => 0,
+        };
+        if self.shown == Some((filled, color)) {
+            return;
+        }
+        if filled > old_filled {
+            rend.fill_rect(Layer::Foreground, self.bar(old_filled, filled), color);
+        } else if filled < old_filled {
+            rend.fill_rect(Layer::Foreground,
+                           self.bar(filled, old_filled),
+                           color::TRANSPARENT);
+        }
+        let mark_y = self.rect.y + height as i32 / 2;
+        rend.fill_rect(Layer::Foreground,
+                       Rect::new(self.rect.x, mark_y, self.rect.width, 1),
+                       color::WHITE);
+        self.shown = Some((filled, color));
+    }
+
+    pub fn hide(&mut self, rend: &mut Renderer) {
+        if self.shown.is_some() {
+            rend.fill_rect(Layer::Foreground, self.rect, color::TRANSPARENT);
+            self.shown = None;
+        }
+    }
+
+    // the part of the bar between the two fill heights, counted from below
+    fn bar(&self, from: u16, to: u16) -> Rect {
+        let bottom = self.rect.y + self.rect.height as i32;
+        Rect::new(self.rect.x, bottom - to as i32, self.rect.width, to - from)
+    }
+}
@@ -0,0 +1,129 @@
+use core::ptr;
+use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering, ATOMIC_BOOL_INIT, ATOMIC_USIZE_INIT};
+
+// layer registers of the lcd-tft controller (RM0385, chapter 18); the
+// stm32f7 crate sets them up once in lcd::init and offers no way to change
+// them afterwards
+const LTDC_BASE: u32 = 0x4001_6800;
+const AWCR: u32 = 0x0C;
+const SRCR: u32 = 0x24;
+const IER: u32 = 0x34;
+const ICR: u32 = 0x3C;
+const LIPCR: u32 = 0x40;
+const LAYER_1: u32 = 0x84;
+const LAYER_STRIDE: u32 = 0x80;
+const LXCR: u32 = 0x00;
+const LXCACR: u32 = 0x14;
+const LXBFCR: u32 = 0x1C;
+const LXCFBAR: u32 = 0x28;
+
+const SRCR_IMR: u32 = 1 << 0;
+const CR_LEN: u32 = 1 << 0;
+const IER_LIE: u32 = 1 << 0;
+const ICR_CLIF: u32 = 1 << 0;
+const AWCR_AAH_MASK: u32 = 0x7FF;
+
+// blend with pixel alpha * constant alpha, so the constant alpha fades
+// the whole layer
+const BFCR_PIXEL_TIMES_CONSTANT: u32 = (0b110 << 8) | 0b111;
+
+// layer 1 is at the bottom, layer 2 is drawn on top of it
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum LayerIndex {
+    Layer1 = 0,
+    Layer2 = 1,
+}
+
+pub fn set_enabled(layer: LayerIndex, enabled: bool) {
+    let cr = read_reg(layer, LXCR);
+    if enabled {
+        write_reg(layer, LXCR, cr | CR_LEN);
+    } else {
+        write_reg(layer, LXCR, cr & !CR_LEN);
+    }
+}
+
+pub fn set_constant_alpha(layer: LayerIndex, alpha: u8) {
+    write_reg(layer, LXBFCR, BFCR_PIXEL_TIMES_CONSTANT);
+    write_reg(layer, LXCACR, alpha as u32);
+}
+
+pub fn set_framebuffer(layer: LayerIndex, addr: u32) {
+    write_reg(layer, LXCFBAR, addr);
+}
+
+// changes are written to shadow registers and only take effect on reload,
+// this latches them right away and waits until the ltdc took them over
+pub fn reload() {
+    let srcr = (LTDC_BASE + SRCR) as *mut u32;
+    unsafe {
+        ptr::write_volatile(srcr, SRCR_IMR);
+        // the ltdc clears the flag once the reload happened
+        while ptr::read_volatile(srcr) & SRCR_IMR != 0 {}
+    }
+}
+
+// a reload waiting for the next vertical blanking period, and the layer 2
+// framebuffer it switches to (0 keeps the current one). both are set by
+// `queue_reload` and taken over by the line interrupt.
+static RELOAD_QUEUED: AtomicBool = ATOMIC_BOOL_INIT;
+static QUEUED_FRAMEBUFFER: AtomicUsize = ATOMIC_USIZE_INIT;
+
+// raises the line interrupt on the first line after the active area, so
+// `on_line_interrupt` runs at the start of every vertical blanking period
+pub fn enable_blanking_interrupt() {
+    // accumulated active height, the last line of the active area
+    let last_active_line = read_global_reg(AWCR) & AWCR_AAH_MASK;
+    write_global_reg(LIPCR, last_active_line + 1);
+    write_global_reg(ICR, ICR_CLIF);
+    write_global_reg(IER, read_global_reg(IER) | IER_LIE);
+    reload();
+}
+
+// reloads the shadow registers at the start of the next vertical blanking
+// period without waiting for it; `framebuffer` becomes the new layer 2
+// framebuffer then
+pub fn queue_reload(framebuffer: Option<u32>) {
+    QUEUED_FRAMEBUFFER.store(framebuffer.unwrap_or(0) as usize, Ordering::SeqCst);
+    RELOAD_QUEUED.store(true, Ordering::SeqCst);
+}
+
+// true until the line interrupt applied the last queued reload
+pub fn reload_queued() -> bool {
+    RELOAD_QUEUED.load(Ordering::SeqCst)
+}
+
+// handler of the ltdc global interrupt, we are in the blanking period, so
+// nothing is scanned out while the registers change
+pub fn on_line_interrupt() {
+    write_global_reg(ICR, ICR_CLIF);
+    if !RELOAD_QUEUED.load(Ordering::SeqCst) {
+        return;
+    }
+    let framebuffer = QUEUED_FRAMEBUFFER.load(Ordering::SeqCst) as u32;
+    if framebuffer != 0 {
+        set_framebuffer(LayerIndex::Layer2, framebuffer);
+    }
+    reload();
+    RELOAD_QUEUED.store(false, Ordering::SeqCst);
+}
+
+fn write_global_reg(offset: u32, value: u32) {
+    unsafe { ptr::write_volatile((LTDC_BASE + offset) as *mut u32, value) };
+}
+
+fn read_global_reg(offset: u32) -> u32 {
+    unsafe { ptr::read_volatile((LTDC_BASE + offset) as *const u32) }
+}
+
+fn layer_reg(layer: LayerIndex, offset: u32) -> u32 {
+    LTDC_BASE + LAYER_1 + layer as u32 * LAYER_STRIDE + offset
+}
+
+fn write_reg(layer: LayerIndex, offset: u32, value: u32) {
+    unsafe { ptr::write_volatile(layer_reg(layer, offset) as *mut u32, value) };
+}
+
+fn read_reg(layer: LayerIndex, offset: u32) -> u32 {
+    unsafe { ptr::read_volatile(layer_reg(layer, offset) as *const u32) }
+}
@@ -16,8 +16,32 @@ pub mod seven_segment;
 pub mod random;
 pub mod constants;
 pub mod game;
-
-use stm32f7::{system_clock, sdram, lcd, i2c, audio, touch, board, embedded};
+pub mod score;
+pub mod dma2d;
+pub mod ltdc;
+pub mod geometry;
+pub mod color;
+pub mod math;
+pub mod primitives;
+pub mod font;
+pub mod animation;
+pub mod particles;
+pub mod popups;
+pub mod microphone;
+pub mod detector;
+pub mod classifier;
+pub mod level_meter;
+pub mod sound;
+pub mod mixer;
+pub mod speaker;
+#[cfg(test)]
+pub mod wav;
+pub mod transform;
+pub mod transition;
+
+use stm32f7::{system_clock, sdram, lcd, i2c, audio, touch, board, embedded, interrupts};
+use stm32f7::interrupts::interrupt_request::InterruptRequest;
+use stm32f7::interrupts::Priority;
 use collections::vec::Vec;
 use embedded::interfaces::gpio::Gpio; // {self, Gpio} for use with button
 
@@ -69,6 +93,7 @@ fn main(hw: board::Hardware) -> ! {
                           gpio_k,
                           i2c_3,
                           sai_2,
+                          nvic,
                           .. } = hw;
 
     let mut gpio = Gpio::new(gpio_a,
@@ -123,6 +148,11 @@ fn main(hw: board::Hardware) -> ! {
     audio::init_sai_2_pins(&mut gpio);
     audio::init_sai_2(sai_2, rcc);
     assert!(audio::init_wm8994(&mut i2c_3).is_ok());
+    let mut mic = microphone::Microphone::new(sai_2,
+                                              rcc,
+                                              constants::SHOT_DETECTOR,
+                                              constants::SOUND_CLASSIFIER);
+    let speaker = speaker::Speaker::new(sai_2, rcc, &mut i2c_3, constants::MASTER_VOLUME);
 
     // initialize random number generator and pseudo
     // random number generator
@@ -133,56 +163,78 @@ fn main(hw: board::Hardware) -> ! {
     }
     let rand = random::MTRng32::new(seed.unwrap());
 
-    //renderer
-    let mut rend = renderer::Renderer::new(lcd);
-    rend.draw_dump_bg(0,
-                      0,
-                      (constants::DISPLAY_SIZE.0, constants::DISPLAY_SIZE.1),
-                      constants::BACKGROUND);
-
-    //create and init game
-    let mut game = game::Game::new(rend, rand);
-
-    // draw game banner
-    game.draw_game_banner();
-    loop {
-        if !touch::touches(&mut i2c_3).unwrap().is_empty() {
-            break;
+    // chrom-art accelerator for fills and blits
+    let dma2d = dma2d::Dma2d::init(rcc);
+
+    // the foreground buffers are swapped by the ltdc interrupt at the start
+    // of vertical blanking
+    interrupts::scope(nvic, |_| {}, |interrupt_table| {
+        interrupt_table.register(InterruptRequest::LcdTft, Priority::P1, ltdc::on_line_interrupt)
+            .expect("ltdc interrupt already in use");
+        ltdc::enable_blanking_interrupt();
+
+        //renderer
+        let mut rend = renderer::Renderer::new(Some(dma2d));
+        rend.draw_dump_bg(0,
+                          0,
+                          (constants::DISPLAY_SIZE.0, constants::DISPLAY_SIZE.1),
+                          constants::BACKGROUND);
+        rend.set_double_buffered(constants::DOUBLE_BUFFERING);
+
+        //create and init game
+        let mut game = game::Game::new(rend, rand, speaker);
+
+        // draw game banner
+        game.draw_game_banner();
+        game.present();
+        loop {
+            if !touch::touches(&mut i2c_3).unwrap().is_empty() {
+                break;
+            }
         }
-    }
 
-    // switch to start screen
-    game.draw_start_banner();
-    let mut game_running = false;
-    let mut touches_to_start = 1;
-
-    // loop game
-    loop {
-        let mut touches: Vec<(u16, u16)> = Vec::new();
-        for touch in &touch::touches(&mut i2c_3).unwrap() {
-            touches.push((touch.x, touch.y));
-        }
+        // switch to start screen, nobody shoots there, so it is a good moment
+        // to listen to the room
+        game.draw_start_banner();
+        game.present();
+        mic.calibrate(constants::MIC_CALIBRATION_WINDOWS);
+        let mut game_running = false;
+        let mut touches_to_start = 1;
+
+        // loop game
+        loop {
+            let mut touches: Vec<(u16, u16)> = Vec::new();
+            for touch in &touch::touches(&mut i2c_3).unwrap() {
+                touches.push((touch.x, touch.y));
+            }
 
-        if game_running {
-            if 0 < game.update_countdown() {
-                game.draw_missing_targets();
-                game.process_shooting(sai_2, touches);
-                game.purge_old_targets();
-            } else {
-                game.game_over();
-                game_running = false;
+            if game_running {
+                if 0 < game.update_countdown() {
+                    game.draw_missing_targets();
+                    game.process_shooting(&mut mic, touches);
+                    game.purge_old_targets();
+                } else {
+                    game.game_over();
+                    game_running = false;
+                }
+            } else if !touches.is_empty() && touches_to_start > 2 {
+                touches_to_start -= 1;
+                stm32f7::system_clock::wait(250);
+            } else if !touches.is_empty() && touches_to_start == 2 {
+                game.draw_start_banner();
+                touches_to_start -= 1;
+            } else if !touches.is_empty() && touches_to_start == 1 {
+                let touch = touches.pop().unwrap();
+                if game::Game::sound_setting_touched(touch) {
+                    game.toggle_sound();
+                } else {
+                    game.start(touch, &mut mic);
+                    game_running = true;
+                    touches_to_start = 3;
+                }
             }
-        } else if !touches.is_empty() && touches_to_start > 2 {
-            touches_to_start -= 1;
-            stm32f7::system_clock::wait(250);
-        } else if !touches.is_empty() && touches_to_start == 2 {
-            game.draw_start_banner();
-            touches_to_start -= 1;
-        } else if !touches.is_empty() && touches_to_start == 1 {
-            game.start(touches.pop().unwrap());
-            game_running = true;
-            touches_to_start = 3;
+            game.present();
         }
-    }
-
+    });
+    unreachable!();
 }
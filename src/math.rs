@@ -0,0 +1,45 @@
+// integer square root, rounded down
+pub fn isqrt(n: u32) -> u32 {
+    if n < 4 {
+        return if n == 0 { 0 } else { 1 };
+    }
+    // newton iteration, starting above the root so it decreases monotonically
+    let mut x = n;
+    let mut y = n / 2 + 1;
+    while y < x {
+        x = y;
+        y = (x + n / x) / 2;
+    }
+    x
+}
+
+// sine values for 0 to 90 degrees in Q14 fixed point, core has no sin/cos
+static SIN_TABLE: [i32; 91] = [
+    0, 286, 572, 857, 1143, 1428, 1713, 1997, 2280, 2563,
+    2845, 3126, 3406, 3686, 3964, 4240, 4516, 4790, 5063, 5334,
+    5604, 5872, 6138, 6402, 6664, 6924, 7182, 7438, 7692, 7943,
+    8192, 8438, 8682, 8923, 9162, 9397, 9630, 9860, 10087, 10311,
+    10531, 10749, 10963, 11174, 11381, 11585, 11786, 11982, 12176, 12365,
+    12551, 12733, 12911, 13085, 13255, 13421, 13583, 13741, 13894, 14044,
+    14189, 14330, 14466, 14598, 14726, 14849, 14968, 15082, 15191, 15296,
+    15396, 15491, 15582, 15668, 15749, 15826, 15897, 15964, 16026, 16083,
+    16135, 16182, 16225, 16262, 16294, 16322, 16344, 16362, 16374, 16382,
+    16384,
+];
+
+pub const Q14_ONE: i32 = 1 << 14;
+
+// sine of an angle in whole degrees, in Q14 fixed point
+pub fn sin_deg(degrees: i32) -> i32 {
+    let d = ((degrees % 360) + 360) % 360;
+    match d {
+        0...90 => SIN_TABLE[d as usize],
+        91...180 => SIN_TABLE[(180 - d) as usize],
+        181...270 => -SIN_TABLE[(d - 180) as usize],
+        _ => -SIN_TABLE[(360 - d) as usize],
+    }
+}
+
+pub fn cos_deg(degrees: i32) -> i32 {
+    sin_deg(degrees + 90)
+}
@@ -0,0 +1,191 @@
+// shot trigger on the stereo microphone of the board
+//
+// dma2 copies the samples of both channels from sai2 block b into a ring
+// buffer in the background. the game loop only analyses the windows that
+// arrived since its last call and never waits for the sai. the ambient
+// noise is measured on the start screen and keeps adapting while nobody
+// shoots. every shot is followed until it ends to tell what kind of sound
+// it was.
+use core::ptr;
+use stm32f7::board::sai::Sai;
+use stm32f7::board::rcc::Rcc;
+use constants;
+use detector::{DetectorConfig, ShotDetector};
+use classifier::{Classifier, ClassifierConfig, Sound};
+
+// stream 7, channel 0 of dma2 serves sai2 block b (RM0385, chapter 8)
+const DMA2_BASE: u32 = 0x4002_6400;
+const HISR: u32 = 0x04;
+const HIFCR: u32 = 0x0C;
+const S7CR: u32 = 0x10 + 7 * 0x18;
+const S7NDTR: u32 = S7CR + 0x04;
+const S7PAR: u32 = S7CR + 0x08;
+const S7M0AR: u32 = S7CR + 0x0C;
+const S7FCR: u32 = S7CR + 0x14;
+// all interrupt flags of stream 7
+const HIFCR_STREAM_7: u32 = 0b11_1101 << 22;
+// transfer complete of stream 7, set every time the dma wraps around the ring
+const HISR_TCIF7: u32 = 1 << 27;
+
+const CR_EN: u32 = 1 << 0;
+const CR_CIRC: u32 = 1 << 8;
+const CR_MINC: u32 = 1 << 10;
+const CR_PSIZE_16: u32 = 0b01 << 11;
+const CR_MSIZE_16: u32 = 0b01 << 13;
+const CR_PL_HIGH: u32 = 0b10 << 16;
+
+// data register of sai2 block b, samples are right aligned 16 bit
+const SAI2_BDR: u32 = 0x4001_5C40;
+
+const RING_LEN: usize = constants::MIC_RING;
+static mut RING: [i16; RING_LEN] = [0; RING_LEN];
+
+pub struct Microphone {
+    detector: ShotDetector,
+    classifier: Classifier,
+    window: [i16; constants::MIC_WINDOW],
+    read_pos: usize,
+    // write position of the dma at the last overrun check
+    last_write: usize,
+}
+
+impl Microphone {
+    pub fn new(sai: &'static Sai,
+               rcc: &mut Rcc,
+               detector: DetectorConfig,
+               classifier: ClassifierConfig)
+               -> Self {
+        rcc.ahb1enr.update(|r| r.set_dma2en(true));
+        write_reg(S7CR, 0);
+        while read_reg(S7CR) & CR_EN != 0 {}
+        write_reg(HIFCR, HIFCR_STREAM_7);
+        write_reg(S7PAR, SAI2_BDR);
+        write_reg(S7M0AR, unsafe { &RING as *const _ as u32 });
+        write_reg(S7NDTR, RING_LEN as u32);
+        // direct mode, every request moves one sample
+        write_reg(S7FCR, 0);
+        // channel 0 is selected by leaving CHSEL at zero
+        write_reg(S7CR, CR_PL_HIGH | CR_MSIZE_16 | CR_PSIZE_16 | CR_MINC | CR_CIRC | CR_EN);
+        sai.bcr1.update(|r| r.set_dmaen(true));
+
+        Microphone {
+            detector: ShotDetector::new(detector),
+            classifier: Classifier::new(classifier),
+            window: [0; constants::MIC_WINDOW],
+            read_pos: 0,
+            last_write: 0,
+        }
+    }
+
+    // measures the ambient noise over the next `windows` windows, the room
+    // should be quiet meanwhile
+    pub fn calibrate(&mut self, windows: usize) {
+        self.skip_to_latest();
+        self.detector.reset_calibration();
+        let mut done = 0;
+        while done < windows {
+            self.check_overrun();
+            if self.next_window() {
+                self.detector.calibrate(&self.window);
+                done += 1;
+            }
+        }
+    }
+
+    pub fn noise_floor(&self) -> u16 {
+        self.detector.noise_floor()
+    }
+
+    // the current envelope of the rms level
+    pub fn level(&self) -> u16 {
+        self.detector.envelope()
+    }
+
+    // level that triggers a shot
+    pub fn threshold(&self) -> u16 {
+        self.detector.threshold()
+    }
+
+    // analyses everything recorded since the last call and returns the
+    // first sound that could be told apart in it. a voice is returned on
+    // every call until it ends.
+    pub fn poll(&mut self, tick: usize) -> Option<Sound> {
+        self.check_overrun();
+        if self.available() > RING_LEN / 2 {
+            // the game loop was stalled, old sounds are no shots anymore
+            self.skip_to_latest();
+        }
+        let mut sound = None;
+        while self.next_window() {
+            if self.detector.process(&self.window, tick) {
+                self.classifier.start();
+            }
+            let heard = self.classifier
+                .add(&self.window, constants::MIC_CHANNELS, self.detector.is_active());
+            if sound.is_none() {
+                sound = heard;
+            }
+        }
+        sound
+    }
+
+    // position the dma writes to next
+    fn write_pos(&self) -> usize {
+        // the counter runs down from the ring length and reloads at the end
+        (RING_LEN - read_reg(S7NDTR) as usize) % RING_LEN
+    }
+
+    fn available(&self) -> usize {
+        (self.write_pos() + RING_LEN - self.read_pos) % RING_LEN
+    }
+
+    // drops everything recorded so far but the latest window, call it when
+    // polling resumes after a pause
+    pub fn skip_to_latest(&mut self) {
+        write_reg(HIFCR, HISR_TCIF7);
+        let window = self.window.len();
+        self.last_write = self.write_pos();
+        self.read_pos = (self.last_write + RING_LEN - window) % RING_LEN;
+    }
+
+    // skips to the latest window if the dma overwrote samples that were not
+    // read yet. the distance between the positions alone cannot tell a full
+    // lap of the dma, the transfer complete flag can.
+    fn check_overrun(&mut self) {
+        let wrapped = read_reg(HISR) & HISR_TCIF7 != 0;
+        write_reg(HIFCR, HISR_TCIF7);
+        let write_pos = self.write_pos();
+        if !wrapped && write_pos < self.last_write {
+            // it wrapped after the flag was read, do not count that twice
+            write_reg(HIFCR, HISR_TCIF7);
+        }
+        let unread = (self.last_write + RING_LEN - self.read_pos) % RING_LEN;
+        let written = (write_pos + RING_LEN - self.last_write) % RING_LEN;
+        let lapped = wrapped && write_pos >= self.last_write;
+        self.last_write = write_pos;
+        if lapped || unread + written >= RING_LEN {
+            self.skip_to_latest();
+        }
+    }
+
+    // copies the next complete window out of the ring, if there is one
+    fn next_window(&mut self) -> bool {
+        if self.available() < self.window.len() {
+            return false;
+        }
+        for i in 0..self.window.len() {
+            let pos = (self.read_pos + i) % RING_LEN;
+            self.window[i] = unsafe { ptr::read_volatile(&RING[pos]) };
+        }
+        self.read_pos = (self.read_pos + self.window.len()) % RING_LEN;
+        true
+    }
+}
+
+fn write_reg(offset: u32, value: u32) {
+    unsafe { ptr::write_volatile((DMA2_BASE + offset) as *mut u32, value) };
+}
+
+fn read_reg(offset: u32) -> u32 {
+    unsafe { ptr::read_volatile((DMA2_BASE + offset) as *const u32) }
+}
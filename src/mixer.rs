@@ -0,0 +1,109 @@
+// mixes the playing sounds into interleaved output samples
+//
+// every sound has its own volume on top of the master volume. muting keeps
+// the sounds running silently, so the music is still in time afterwards.
+use collections::vec::Vec;
+use sound::{Decoder, Sample};
+
+// sounds playing at the same time, the oldest effect makes room
+const CAPACITY: usize = 4;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Playback(u32);
+
+struct Voice {
+    decoder: Decoder,
+    volume: u8,
+    looping: bool,
+    finished: bool,
+    playback: Playback,
+}
+
+pub struct Mixer {
+    voices: Vec<Voice>,
+    volume: u8,
+    muted: bool,
+    next_playback: u32,
+}
+
+impl Mixer {
+    pub fn new(volume: u8) -> Self {
+        Mixer {
+            voices: Vec::new(),
+            volume: volume,
+            muted: false,
+            next_playback: 0,
+        }
+    }
+
+    // 0 is silent, 255 full volume
+    pub fn play(&mut self, sample: &'static Sample, volume: u8, looping: bool) -> Playback {
+        if self.voices.len() == CAPACITY {
+            // looping music would never come back, effects end anyway
+            let oldest = self.voices.iter().position(|v| !v.looping).unwrap_or(0);
+            self.voices.remove(oldest);
+        }
+        let playback = Playback(self.next_playback);
+        self.next_playback = self.next_playback.wrapping_add(1);
+        self.voices.push(Voice {
+            decoder: Decoder::new(sample),
+            volume: volume,
+            looping: looping,
+            finished: false,
+            playback: playback,
+        });
+        playback
+    }
+
+    pub fn stop(&mut self, playback: Playback) {
+        self.voices.retain(|v| v.playback != playback);
+    }
+
+    pub fn set_volume(&mut self, volume: u8) {
+        self.volume = volume;
+    }
+
+    pub fn volume(&self) -> u8 {
+        self.volume
+    }
+
+    pub fn set_muted(&mut self, muted: bool) {
+        self.muted = muted;
+    }
+
+    pub fn is_muted(&self) -> bool {
+        self.muted
+    }
+
+    // fills `out` with frames of `channels` samples each, every channel gets
+    // the same mono mix
+    pub fn mix(&mut self, out: &mut [i16], channels: usize) {
+        let master = if self.muted { 0 } else { self.volume as i32 };
+        for frame in out.chunks_mut(channels) {
+            let mut sum: i32 = 0;
+            for v in &mut self.voices {
+                let mut value = v.decoder.next();
+                if value.is_none() && v.looping {
+                    v.decoder.rewind();
+                    value = v.decoder.next();
+                }
+                match value {
+                    Some(s) => sum += (s as i32 * v.volume as i32) >> 8,
+                    None => v.finished = true,
+                }
+            }
+            let sum = (sum * master) >> 8;
+            let value = if sum > i16::max_value() as i32 {
+                i16::max_value()
+            } else if sum < i16::min_value() as i32 {
+                i16::min_value()
+            } else {
+                sum as i16
+            };
+            for s in frame.iter_mut() {
+                *s = value;
+            }
+        }
+        self.voices.retain(|v| !v.finished);
+    }
+}
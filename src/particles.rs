@@ -0,0 +1,165 @@
+// fixed capacity particle system for the confetti of a burst pinata
+use animation;
+use color::{self, Color};
+use geometry::Rect;
+use random;
+
+const CAPACITY: usize = 64;
+
+// positions and velocities are stored in 1/1024 px (per ms)
+const FIXED_ONE: i32 = 1024;
+// speed gained per ms, in 1/1024 px per ms
+const GRAVITY: i32 = 1;
+const MAX_SPEED: u32 = 256;
+const LIFETIME: (usize, usize) = (300, 500);
+
+static CONFETTI_COLORS: [Color; 7] = [color::RED,
+                                      color::GREEN,
+                                      color::BLUE,
+                                      color::YELLOW,
+                                      color::ORANGE,
+                                      color::PINK,
+                                      color::PURPLE];
+
+#[derive(Clone, Copy)]
+struct Particle {
+    pos: (i32, i32),
+    velocity: (i32, i32),
+    // confetti are square, candy pieces twice as wide as high
+    size: (u16, u16),
+    color: Color,
+    birthday: usize,
+    lifetime: usize,
+    alive: bool,
+}
+
+const DEAD: Particle = Particle {
+    pos: (0, 0),
+    velocity: (0, 0),
+    size: (0, 0),
+    color: color::TRANSPARENT,
+    birthday: 0,
+    lifetime: 0,
+    alive: false,
+};
+
+impl Particle {
+    // particles shrink while they get older, before their birthday they
+    // have their full size
+    fn rect(&self, tick: usize) -> Rect {
+        let age = tick.saturating_sub(self.birthday);
+        let shrink = (3 * age / self.lifetime) as u16;
+        let w = if self.size.0 > shrink { self.size.0 - shrink } else { 1 };
+        let h = if self.size.1 > shrink { self.size.1 - shrink } else { 1 };
+        Rect::new(self.pos.0 / FIXED_ONE, self.pos.1 / FIXED_ONE, w, h)
+    }
+
+    // fully visible in the first half of the lifetime, then dithered away
+    fn coverage(&self, tick: usize) -> u8 {
+        let half = self.lifetime / 2;
+        let age = tick.saturating_sub(self.birthday);
+        if age < half {
+            animation::FULL_COVERAGE
+        } else {
+            let left = self.lifetime.saturating_sub(age);
+            (animation::FULL_COVERAGE as usize * left / (self.lifetime - half)) as u8
+        }
+    }
+}
+
+pub struct ParticleSystem {
+    particles: [Particle; CAPACITY],
+    last_update: usize,
+}
+
+impl ParticleSystem {
+    pub fn new() -> Self {
+        ParticleSystem {
+            particles: [DEAD; CAPACITY],
+            last_update: 0,
+        }
+    }
+
+    // emits up to `count` particles, the oldest ones are not replaced when
+    // the system is full
+    pub fn emit(&mut self, pos: (i32, i32), count: usize, tick: usize, rng: &mut random::MTRng32) {
+        if !self.particles.iter().any(|p| p.alive) {
+            self.last_update = tick;
+        }
+        let mut emitted = 0;
+        for p in self.particles.iter_mut().filter(|p| !p.alive) {
+            if emitted == count {
+                break;
+            }
+            let candy = rng.rand() % 3 == 0;
+            *p = Particle {
+                pos: (pos.0 * FIXED_ONE, pos.1 * FIXED_ONE),
+                velocity: (random_speed(rng), random_speed(rng) - MAX_SPEED as i32 / 2),
+                size: if candy { (4, 2) } else { (3, 3) },
+                color: CONFETTI_COLORS[rng.rand() as usize % CONFETTI_COLORS.len()],
+                birthday: tick,
+                lifetime: LIFETIME.0 + rng.rand() as usize % (LIFETIME.1 - LIFETIME.0),
+                alive: true,
+            };
+            emitted += 1;
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        !self.particles.iter().any(|p| p.alive)
+    }
+
+    // moves all particles to `tick` and returns the area that changed
+    pub fn update(&mut self, tick: usize) -> Option<Rect> {
+        let previous = self.last_update;
+        self.last_update = tick;
+        let mut changed: Option<Rect> = None;
+        for p in self.particles.iter_mut().filter(|p| p.alive) {
+            // particles emitted since the last update start at their spawn
+            let since = if p.birthday > previous { p.birthday } else { previous };
+            let dt = tick.saturating_sub(since) as i32;
+            let before = p.rect(since);
+            if tick.saturating_sub(p.birthday) >= p.lifetime {
+                p.alive = false;
+                changed = Some(union(changed, before));
+                continue;
+            }
+            p.velocity.1 += GRAVITY * dt;
+            p.pos.0 += p.velocity.0 * dt;
+            p.pos.1 += p.velocity.1 * dt;
+            changed = Some(union(changed, before).union(&p.rect(tick)));
+        }
+        changed
+    }
+
+    // kills all particles and returns the area they covered
+    pub fn clear(&mut self) -> Option<Rect> {
+        let mut changed: Option<Rect> = None;
+        for p in self.particles.iter_mut().filter(|p| p.alive) {
+            changed = Some(union(changed, p.rect(self.last_update)));
+            p.alive = false;
+        }
+        changed
+    }
+
+    // calls `f` with the area, color and coverage of every living particle
+    // as of the last update
+    pub fn for_each<F>(&self, mut f: F)
+        where F: FnMut(Rect, Color, u8)
+    {
+        for p in self.particles.iter().filter(|p| p.alive) {
+            f(p.rect(self.last_update), p.color, p.coverage(self.last_update));
+        }
+    }
+}
+
+fn random_speed(rng: &mut random::MTRng32) -> i32 {
+    (rng.rand() % (2 * MAX_SPEED + 1)) as i32 - MAX_SPEED as i32
+}
+
+fn union(rect: Option<Rect>, other: Rect) -> Rect {
+    match rect {
+        Some(r) => r.union(&other),
+        None => other,
+    }
+}
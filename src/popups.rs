@@ -0,0 +1,136 @@
+// short texts like "+50" that rise from a hit target and fade out
+use collections::string::String;
+use collections::vec::Vec;
+use color::Color;
+use geometry::Rect;
+use font::{self, Align, TextStyle};
+use animation;
+
+const CAPACITY: usize = 8;
+const LIFETIME: usize = 800;
+// distance risen over the lifetime, in px
+const RISE: i32 = 30;
+const SCALE: u16 = 2;
+
+struct Popup {
+    text: String,
+    // top center of the text when it appears
+    pos: (i32, i32),
+    color: Color,
+    birthday: usize,
+}
+
+impl Popup {
+    fn style(&self) -> TextStyle {
+        TextStyle::new(&font::PROPORTIONAL_5X7, self.color)
+            .with_scale(SCALE)
+            .with_align(Align::Center)
+    }
+
+    // popups shown after `tick` have not started rising yet
+    fn age(&self, tick: usize) -> usize {
+        tick.saturating_sub(self.birthday)
+    }
+
+    // top center of the text at `tick`
+    fn pos(&self, tick: usize) -> (i32, i32) {
+        let risen = RISE * self.age(tick) as i32 / LIFETIME as i32;
+        (self.pos.0, self.pos.1 - risen)
+    }
+
+    fn rect(&self, tick: usize) -> Rect {
+        let pos = self.pos(tick);
+        let style = self.style();
+        let width = style.text_width(&self.text);
+        Rect::new(pos.0 - width as i32 / 2,
+                  pos.1,
+                  width,
+                  style.font.height() * style.scale)
+    }
+
+    // fully visible in the first half of the lifetime, then dithered away
+    fn coverage(&self, tick: usize) -> u8 {
+        let half = LIFETIME / 2;
+        let age = self.age(tick);
+        if age < half {
+            animation::FULL_COVERAGE
+        } else {
+            (animation::FULL_COVERAGE as usize * (LIFETIME - age) / half) as u8
+        }
+    }
+}
+
+pub struct PopupSystem {
+    popups: Vec<Popup>,
+    last_update: usize,
+}
+
+impl PopupSystem {
+    pub fn new() -> Self {
+        PopupSystem {
+            popups: Vec::new(),
+            last_update: 0,
+        }
+    }
+
+    // the oldest popup makes room when the system is full; returns the area
+    // it covered
+    pub fn show(&mut self, text: &str, pos: (i32, i32), color: Color, tick: usize) -> Option<Rect> {
+        let mut changed = None;
+        if self.popups.len() == CAPACITY {
+            changed = Some(self.popups.remove(0).rect(self.last_update));
+        }
+        if self.popups.is_empty() {
+            self.last_update = tick;
+        }
+        self.popups.push(Popup {
+            text: String::from(text),
+            pos: pos,
+            color: color,
+            birthday: tick,
+        });
+        changed
+    }
+
+    // moves all popups to `tick` and returns the area that changed
+    pub fn update(&mut self, tick: usize) -> Option<Rect> {
+        let previous = self.last_update;
+        self.last_update = tick;
+        let mut changed: Option<Rect> = None;
+        for p in &self.popups {
+            changed = Some(union(changed, p.rect(previous)));
+            if p.age(tick) < LIFETIME {
+                changed = Some(union(changed, p.rect(tick)));
+            }
+        }
+        self.popups.retain(|p| p.age(tick) < LIFETIME);
+        changed
+    }
+
+    // removes all popups and returns the area they covered
+    pub fn clear(&mut self) -> Option<Rect> {
+        let mut changed: Option<Rect> = None;
+        for p in &self.popups {
+            changed = Some(union(changed, p.rect(self.last_update)));
+        }
+        self.popups.clear();
+        changed
+    }
+
+    // calls `f` with the text, top center position, style and coverage of
+    // every popup as of the last update
+    pub fn for_each<F>(&self, mut f: F)
+        where F: FnMut(&str, (i32, i32), &TextStyle, u8)
+    {
+        for p in &self.popups {
+            f(&p.text, p.pos(self.last_update), &p.style(), p.coverage(self.last_update));
+        }
+    }
+}
+
+fn union(rect: Option<Rect>, other: Rect) -> Rect {
+    match rect {
+        Some(r) => r.union(&other),
+        None => other,
+    }
+}
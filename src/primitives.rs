@@ -0,0 +1,234 @@
+// rasterization of lines and shapes
+//
+// outlines call `plot(x, y)` for every pixel, filled shapes call
+// `span(x_start, x_end, y)` for every row with both ends inclusive.
+use core::cmp;
+use collections::vec::Vec;
+use geometry::Rect;
+use math;
+
+// bresenham
+pub fn line<F>(from: (i32, i32), to: (i32, i32), mut plot: F)
+    where F: FnMut(i32, i32)
+{
+    let dx = (to.0 - from.0).abs();
+    let dy = -(to.1 - from.1).abs();
+    let step_x = if from.0 < to.0 { 1 } else { -1 };
+    let step_y = if from.1 < to.1 { 1 } else { -1 };
+    let mut err = dx + dy;
+    let (mut x, mut y) = from;
+    loop {
+        plot(x, y);
+        if x == to.0 && y == to.1 {
+            break;
+        }
+        let e2 = 2 * err;
+        if e2 >= dy {
+            err += dy;
+            x += step_x;
+        }
+        if e2 <= dx {
+            err += dx;
+            y += step_y;
+        }
+    }
+}
+
+// midpoint circle, calls `f` for every point of the first octant relative to
+// the center
+fn octant<F>(radius: i32, mut f: F)
+    where F: FnMut(i32, i32)
+{
+    let mut x = radius;
+    let mut y = 0;
+    let mut err = 1 - radius;
+    while x >= y {
+        f(x, y);
+        y += 1;
+        if err < 0 {
+            err += 2 * y + 1;
+        } else {
+            x -= 1;
+            err += 2 * (y - x) + 1;
+        }
+    }
+}
+
+pub fn circle<F>(center: (i32, i32), radius: i32, mut plot: F)
+    where F: FnMut(i32, i32)
+{
+    let (cx, cy) = center;
+    octant(radius, |x, y| {
+        plot(cx + x, cy + y);
+        plot(cx + y, cy + x);
+        plot(cx - y, cy + x);
+        plot(cx - x, cy + y);
+        plot(cx - x, cy - y);
+        plot(cx - y, cy - x);
+        plot(cx + y, cy - x);
+        plot(cx + x, cy - y);
+    });
+}
+
+// half of the width of a circle row `dy` rows away from the center
+fn circle_half_width(radius: i32, dy: i32) -> i32 {
+    math::isqrt((radius * radius - dy * dy) as u32) as i32
+}
+
+pub fn filled_circle<F>(center: (i32, i32), radius: i32, mut span: F)
+    where F: FnMut(i32, i32, i32)
+{
+    let (cx, cy) = center;
+    for dy in -radius..radius + 1 {
+        let dx = circle_half_width(radius, dy);
+        span(cx - dx, cx + dx, cy + dy);
+    }
+}
+
+pub fn rect<F>(rect: Rect, mut plot: F)
+    where F: FnMut(i32, i32)
+{
+    if rect.is_empty() {
+        return;
+    }
+    let (right, bottom) = (rect.right() - 1, rect.bottom() - 1);
+    for x in rect.x..right + 1 {
+        plot(x, rect.y);
+        plot(x, bottom);
+    }
+    for y in rect.y + 1..bottom {
+        plot(rect.x, y);
+        plot(right, y);
+    }
+}
+
+// the radius is clamped to half of the shorter side
+pub fn rounded_rect<F>(rect: Rect, radius: i32, mut plot: F)
+    where F: FnMut(i32, i32)
+{
+    if rect.is_empty() {
+        return;
+    }
+    let r = clamp_radius(rect, radius);
+    let (left, top) = (rect.x, rect.y);
+    let (right, bottom) = (rect.right() - 1, rect.bottom() - 1);
+    for x in left + r..right - r + 1 {
+        plot(x, top);
+        plot(x, bottom);
+    }
+    for y in top + r..bottom - r + 1 {
+        plot(left, y);
+        plot(right, y);
+    }
+    octant(r, |x, y| {
+        plot(left + r - x, top + r - y);
+        plot(left + r - y, top + r - x);
+        plot(right - r + x, top + r - y);
+        plot(right - r + y, top + r - x);
+        plot(right - r + x, bottom - r + y);
+        plot(right - r + y, bottom - r + x);
+        plot(left + r - x, bottom - r + y);
+        plot(left + r - y, bottom - r + x);
+    });
+}
+
+pub fn filled_rounded_rect<F>(rect: Rect, radius: i32, mut span: F)
+    where F: FnMut(i32, i32, i32)
+{
+    if rect.is_empty() {
+        return;
+    }
+    let r = clamp_radius(rect, radius);
+    let (top, bottom) = (rect.y, rect.bottom() - 1);
+    for y in top..bottom + 1 {
+        let dy = if y < top + r {
+            top + r - y
+        } else if y > bottom - r {
+            y - (bottom - r)
+        } else {
+            0
+        };
+        let inset = r - circle_half_width(r, dy);
+        span(rect.x + inset, rect.right() - 1 - inset, y);
+    }
+}
+
+fn clamp_radius(rect: Rect, radius: i32) -> i32 {
+    let max = (cmp::min(rect.width, rect.height) as i32 - 1) / 2;
+    if radius < 0 {
+        0
+    } else if radius > max {
+        max
+    } else {
+        radius
+    }
+}
+
+// closed outline through all points
+pub fn polygon<F>(points: &[(i32, i32)], mut plot: F)
+    where F: FnMut(i32, i32)
+{
+    for i in 0..points.len() {
+        let next = points[(i + 1) % points.len()];
+        line(points[i], next, &mut plot);
+    }
+}
+
+// scanline fill with the even-odd rule, works for concave polygons as well
+pub fn filled_polygon<F>(points: &[(i32, i32)], mut span: F)
+    where F: FnMut(i32, i32, i32)
+{
+    if points.len() < 3 {
+        return;
+    }
+    let min_y = points.iter().map(|p| p.1).min().unwrap();
+    let max_y = points.iter().map(|p| p.1).max().unwrap();
+    let mut crossings: Vec<i32> = Vec::new();
+    for y in min_y..max_y + 1 {
+        crossings.clear();
+        for i in 0..points.len() {
+            let (x0, y0) = points[i];
+            let (x1, y1) = points[(i + 1) % points.len()];
+            // half open, so shared vertices are only counted once. the
+            // bottom row takes the edges ending in it instead of the ones
+            // starting there, so it is filled like every other row.
+            let crosses = if y == max_y {
+                (y0 < y && y <= y1) || (y1 < y && y <= y0)
+            } else {
+                (y0 <= y && y < y1) || (y1 <= y && y < y0)
+            };
+            if crosses {
+                crossings.push(x0 + (y - y0) * (x1 - x0) / (y1 - y0));
+            }
+        }
+        crossings.sort();
+        for pair in crossings.chunks(2) {
+            if pair.len() == 2 {
+                span(pair[0], pair[1], y);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn polygon_spans(points: &[(i32, i32)]) -> Vec<(i32, i32, i32)> {
+        let mut spans = Vec::new();
+        filled_polygon(points, |x0, x1, y| spans.push((x0, x1, y)));
+        spans
+    }
+
+    #[test]
+    fn rect_covers_all_rows() {
+        assert_eq!(polygon_spans(&[(2, 1), (6, 1), (6, 4), (2, 4)]),
+                   [(2, 6, 1), (2, 6, 2), (2, 6, 3), (2, 6, 4)]);
+    }
+
+    #[test]
+    fn triangle_ends_in_its_tip() {
+        assert_eq!(polygon_spans(&[(0, 0), (4, 0), (2, 2)]),
+                   [(0, 4, 0), (1, 3, 1), (2, 2, 2)]);
+    }
+}
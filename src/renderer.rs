@@ -1,202 +1,1102 @@
-use stm32f7::lcd;
 use core::ptr;
 use core::cmp;
+use core::mem;
+use collections::vec::Vec;
 use constants;
 use random;
+use dma2d::{Dma2d, PixelFormat};
+use geometry::Rect;
+use color::{self, Color};
+use primitives;
+use font::{self, Align, TextStyle};
+use animation::{self, Animation, AnimationState, Frame};
+use particles::ParticleSystem;
+use popups::PopupSystem;
+use transform::Transform;
+use ltdc::{self, LayerIndex};
+use transition::{Effect, Transition};
+
+// framebuffers of the two lcd layers, set up by stm32f7::lcd
+const BG_ADDR: u32 = 0xC000_0000;
+const FG_ADDR: u32 = BG_ADDR + 480 * 272 * 2;
+// off-screen foreground buffer used in double buffered mode
+const FG_BACK_ADDR: u32 = FG_ADDR + 480 * 272 * 2;
+const FRAMEBUFFER_BYTES: u32 = 480 * 272 * 2;
+// transitions show a mix of the old scene snapshot and the new scene
+const MIX_ADDR: u32 = FG_BACK_ADDR + FRAMEBUFFER_BYTES;
+const SNAPSHOT_ADDR: u32 = MIX_ADDR + FRAMEBUFFER_BYTES;
+
+// changed areas tracked separately, further ones are merged
+const MAX_FG_CHANGES: usize = 16;
+
+// pics/convert.py stores this alpha value for images without alpha channel
+const OPAQUE_DUMP_ALPHA: u8 = 42;
 
 pub struct Renderer {
-    display: lcd::Lcd,
+    dma2d: Option<Dma2d>,
     last_touch: (u16, u16),
+    fg_front: u32,
+    fg_draw: u32,
+    // areas drawn to `fg_draw` since it showed the same as `fg_front`
+    fg_changes: Vec<Rect>,
+    // areas the draw buffer misses once the queued flip happened
+    fg_stale: Vec<Rect>,
+    double_buffered: bool,
+    layers: [LayerState; 2],
+    layers_modified: bool,
+    transition: Option<Transition>,
+    sprites: Vec<Option<Sprite>>,
+    dirty: Vec<Rect>,
+    reserved: Vec<Rect>,
+    particles: ParticleSystem,
+    popups: PopupSystem,
+    // address and length of every dump checked for a real alpha channel
+    opaque_dumps: Vec<(usize, usize, bool)>,
+}
+
+pub type SpriteId = usize;
+
+// the background is lcd layer 1, sprites and the hud live on the
+// foreground, layer 2
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Layer {
+    Background,
+    Foreground,
+}
+
+impl Layer {
+    fn index(&self) -> LayerIndex {
+        match *self {
+            Layer::Background => LayerIndex::Layer1,
+            Layer::Foreground => LayerIndex::Layer2,
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+struct LayerState {
+    opacity: u8,
+    visible: bool,
+}
+
+const LAYER_DEFAULT: LayerState = LayerState {
+    opacity: 255,
+    visible: true,
+};
+
+#[derive(Clone, Copy)]
+struct Sprite {
+    x: i32,
+    y: i32,
+    size: (u16, u16),
+    img: &'static [u8],
+    animation: Option<AnimationState>,
+    frame: Frame,
+    remove_when_done: bool,
+    transform: Option<Transform>,
+}
+
+impl Sprite {
+    fn rect(&self) -> Rect {
+        let size = match self.transform {
+            Some(ref t) => t.output_size(),
+            None => self.size,
+        };
+        Rect::new(self.x, self.y, size.0, size.1)
+    }
 }
 
 impl Renderer {
-    pub fn new(display: lcd::Lcd) -> Self {
+    // without dma2d every pixel is written by the cpu
+    pub fn new(dma2d: Option<Dma2d>) -> Self {
         Renderer {
-            display: display,
+            dma2d: dma2d,
             last_touch: (240, 136),
+            fg_front: FG_ADDR,
+            fg_draw: FG_ADDR,
+            fg_changes: Vec::new(),
+            fg_stale: Vec::new(),
+            double_buffered: false,
+            layers: [LAYER_DEFAULT; 2],
+            layers_modified: false,
+            transition: None,
+            sprites: Vec::new(),
+            dirty: Vec::new(),
+            reserved: Vec::new(),
+            particles: ParticleSystem::new(),
+            popups: PopupSystem::new(),
+            opaque_dumps: Vec::new(),
+        }
+    }
+
+    // in double buffered mode the foreground is composed off-screen and only
+    // becomes visible with the next call to `present`
+    pub fn set_double_buffered(&mut self, enabled: bool) {
+        if enabled == self.double_buffered {
+            return;
+        }
+        self.sync_draw_buffer();
+        self.double_buffered = enabled;
+        if enabled {
+            self.fg_draw = Self::other_fg_buffer(self.fg_front);
+            self.copy_framebuffer(self.fg_front, self.fg_draw);
+        } else {
+            if self.transition.is_some() {
+                self.end_transition();
+                ltdc::set_framebuffer(LayerIndex::Layer2, self.fg_front);
+                self.layers_modified = true;
+            }
+            self.fg_draw = self.fg_front;
+            if self.layers_modified {
+                ltdc::reload();
+                self.layers_modified = false;
+            }
+        }
+        self.fg_changes.clear();
+    }
+
+    // composes the scene and, in double buffered mode, queues swapping the
+    // foreground buffers for the next vertical blanking period. the ltdc
+    // interrupt does the swap, we only wait for it once the other buffer is
+    // drawn to.
+    pub fn present(&mut self) {
+        self.compose();
+        if let Some(t) = self.transition {
+            if !t.is_done() {
+                self.present_transition(t);
+                return;
+            }
+            self.end_transition();
+        }
+        if !self.double_buffered || self.fg_changes.is_empty() {
+            if self.layers_modified {
+                ltdc::queue_reload(None);
+                self.layers_modified = false;
+            }
+            return;
+        }
+        ltdc::queue_reload(Some(self.fg_draw));
+        self.layers_modified = false;
+        self.fg_front = self.fg_draw;
+        self.fg_draw = Self::other_fg_buffer(self.fg_front);
+        // we draw incrementally, so the next frame has to start from the
+        // current one
+        self.fg_stale = mem::replace(&mut self.fg_changes, Vec::new());
+    }
+
+    // the foreground buffer to draw to, once it is no longer shown and caught
+    // up with what the shown one got since
+    fn draw_buffer(&mut self) -> u32 {
+        self.sync_draw_buffer();
+        self.fg_draw
+    }
+
+    fn sync_draw_buffer(&mut self) {
+        if !ltdc::reload_queued() && self.fg_stale.is_empty() {
+            return;
+        }
+        while ltdc::reload_queued() {}
+        let stale = mem::replace(&mut self.fg_stale, Vec::new());
+        let (src, dst) = (self.fg_front, self.fg_draw);
+        for rect in &stale {
+            self.copy_rect(src, dst, *rect);
         }
     }
 
+    // remembers what was drawn to the foreground draw buffer, so the other
+    // buffer can catch up after the next swap
+    fn changed(&mut self, layer: u32, rect: Rect) {
+        if !self.double_buffered || layer != self.fg_draw {
+            return;
+        }
+        if self.fg_changes.iter().any(|r| r.contains_rect(&rect)) {
+            return;
+        }
+        self.fg_changes.retain(|r| !rect.contains_rect(r));
+        if self.fg_changes.len() == MAX_FG_CHANGES {
+            // pixels of one shape are drawn one after the other, so the
+            // last area is most likely close by
+            let last = self.fg_changes.pop().unwrap();
+            self.fg_changes.push(last.union(&rect));
+        } else {
+            self.fg_changes.push(rect);
+        }
+    }
+
+    // 0 is fully transparent, 255 fully opaque
+    pub fn set_layer_opacity(&mut self, layer: Layer, opacity: u8) {
+        self.layers[layer.index() as usize].opacity = opacity;
+        ltdc::set_constant_alpha(layer.index(), opacity);
+        self.reload_layers();
+    }
+
+    pub fn layer_opacity(&self, layer: Layer) -> u8 {
+        self.layers[layer.index() as usize].opacity
+    }
+
+    // hidden layers keep their content, drawing to them still works
+    pub fn set_layer_visible(&mut self, layer: Layer, visible: bool) {
+        self.layers[layer.index() as usize].visible = visible;
+        ltdc::set_enabled(layer.index(), visible);
+        self.reload_layers();
+    }
+
+    pub fn layer_is_visible(&self, layer: Layer) -> bool {
+        self.layers[layer.index() as usize].visible
+    }
+
+    // address of the framebuffer that is currently drawn to
+    pub fn layer_framebuffer(&mut self, layer: Layer) -> u32 {
+        self.layer_addr(layer)
+    }
+
+    // makes the whole layer transparent, the lcd background color shows
+    // through where both layers are cleared
+    pub fn clear_layer(&mut self, layer: Layer) {
+        self.fill_rect(layer, Self::display_rect(), color::TRANSPARENT);
+    }
+
+    // in double buffered mode layer changes wait for `present`, so they
+    // show up together with the frame
+    fn reload_layers(&mut self) {
+        if self.double_buffered {
+            self.layers_modified = true;
+        } else {
+            ltdc::reload();
+        }
+    }
+
+    // starts switching from the currently shown scene to whatever is drawn
+    // from now on; the effect advances with `animate` and `present`, so the
+    // game keeps running meanwhile. without double buffering the new scene
+    // simply replaces the old one.
+    pub fn start_transition(&mut self, effect: Effect, tick: usize) {
+        if !self.double_buffered {
+            return;
+        }
+        // a running transition is interrupted where it is
+        self.sync_draw_buffer();
+        let shown = if self.transition.is_some() { MIX_ADDR } else { self.fg_front };
+        self.copy_framebuffer(shown, SNAPSHOT_ADDR);
+        self.end_transition();
+        self.transition = Some(Transition::new(effect, tick, constants::TRANSITION_DURATION));
+    }
+
+    pub fn in_transition(&self) -> bool {
+        self.transition.is_some()
+    }
+
+    fn present_transition(&mut self, t: Transition) {
+        // the mix of the last frame may still be on screen
+        self.sync_draw_buffer();
+        let width = constants::DISPLAY_SIZE.0;
+        let columns = t.columns(width);
+        let new_scene = self.fg_draw;
+        match t.effect {
+            Effect::Fade => {
+                let src = if t.shows_new_scene() { new_scene } else { SNAPSHOT_ADDR };
+                self.copy_columns(src, 0, MIX_ADDR, 0, width);
+                let alpha = t.fade_alpha() as u32;
+                for layer in &[Layer::Background, Layer::Foreground] {
+                    let opacity = self.layer_opacity(*layer) as u32 * alpha / 255;
+                    ltdc::set_constant_alpha(layer.index(), opacity as u8);
+                }
+            }
+            Effect::Wipe => {
+                self.copy_columns(new_scene, 0, MIX_ADDR, 0, columns);
+                self.copy_columns(SNAPSHOT_ADDR, columns, MIX_ADDR, columns, width - columns);
+            }
+            Effect::Slide => {
+                self.copy_columns(SNAPSHOT_ADDR, columns, MIX_ADDR, 0, width - columns);
+                self.copy_columns(new_scene, 0, MIX_ADDR, width - columns, columns);
+            }
+        }
+        ltdc::queue_reload(Some(MIX_ADDR));
+        self.layers_modified = false;
+    }
+
+    // shows the new scene with the next regular flip
+    fn end_transition(&mut self) {
+        if let Some(t) = self.transition.take() {
+            if t.effect == Effect::Fade {
+                for layer in &[Layer::Background, Layer::Foreground] {
+                    let opacity = self.layer_opacity(*layer);
+                    ltdc::set_constant_alpha(layer.index(), opacity);
+                }
+                self.layers_modified = true;
+            }
+            // the new scene differs from the shown buffer everywhere
+            self.fg_changes.clear();
+            self.fg_changes.push(Self::display_rect());
+        }
+    }
+
+    // copies `width` full height columns starting at `src_x` of `src` to
+    // `dst_x` of `dst`
+    fn copy_columns(&mut self, src: u32, src_x: u16, dst: u32, dst_x: u16, width: u16) {
+        if width == 0 {
+            return;
+        }
+        let skip = constants::DISPLAY_SIZE.0 - width;
+        let src = src + src_x as u32 * 2;
+        let dst = dst + dst_x as u32 * 2;
+        if let Some(ref mut dma2d) = self.dma2d {
+            dma2d.copy(src, skip, dst, skip, (width, constants::DISPLAY_SIZE.1));
+            return;
+        }
+        for y in 0..constants::DISPLAY_SIZE.1 as u32 {
+            for x in 0..width as u32 {
+                let offset = (y * 480 + x) * 2;
+                unsafe {
+                    let pixel = ptr::read_volatile((src + offset) as *const u16);
+                    ptr::write_volatile((dst + offset) as *mut u16, pixel);
+                }
+            }
+        }
+    }
+
+    fn other_fg_buffer(addr: u32) -> u32 {
+        if addr == FG_ADDR { FG_BACK_ADDR } else { FG_ADDR }
+    }
+
+    fn copy_rect(&mut self, src: u32, dst: u32, rect: Rect) {
+        let offset = (rect.y as u32 * 480 + rect.x as u32) * 2;
+        if let Some(ref mut dma2d) = self.dma2d {
+            let skip = constants::DISPLAY_SIZE.0 - rect.width;
+            dma2d.copy(src + offset, skip, dst + offset, skip, (rect.width, rect.height));
+            return;
+        }
+        for y in rect.y..rect.bottom() {
+            for x in rect.x..rect.right() {
+                let pixel = (y as u32 * 480 + x as u32) * 2;
+                unsafe {
+                    let color = ptr::read_volatile((src + pixel) as *const u16);
+                    ptr::write_volatile((dst + pixel) as *mut u16, color);
+                }
+            }
+        }
+    }
+
+    fn copy_framebuffer(&mut self, src: u32, dst: u32) {
+        if let Some(ref mut dma2d) = self.dma2d {
+            dma2d.copy(src, 0, dst, 0, constants::DISPLAY_SIZE);
+            return;
+        }
+        for i in 0..(FRAMEBUFFER_BYTES / 4) {
+            unsafe {
+                let word = ptr::read_volatile((src + i * 4) as *const u32);
+                ptr::write_volatile((dst + i * 4) as *mut u32, word);
+            }
+        }
+    }
+
+    fn write_pixel(layer: u32, x: u16, y: u16, color: u16) {
+        let pixel = (y as u32) * 480 + (x as u32);
+        let pixel_color = (layer + pixel * 2) as *mut u16;
+        unsafe { ptr::write_volatile(pixel_color, color) };
+    }
+
     fn coord_is_inside(x: u16, y: u16) -> bool {
         x < constants::DISPLAY_SIZE.0 && y < constants::DISPLAY_SIZE.1
     }
 
-    pub fn render_bg(&mut self, x: u16, y: u16, color: u16) {
+    pub fn render_bg(&mut self, x: u16, y: u16, color: Color) {
         if Self::coord_is_inside(x, y) {
-            let addr: u32 = 0xC000_0000;
-            let pixel = (y as u32) * 480 + (x as u32);
-            let pixel_color = (addr + pixel * 2) as *mut u16;
-            unsafe { ptr::write_volatile(pixel_color, color) };
+            let addr = self.layer_addr(Layer::Background);
+            Self::write_pixel(addr, x, y, color.to_argb1555());
         }
     }
 
-    pub fn render_pixel(&mut self, x: u16, y: u16, color: u16) {
+    pub fn render_pixel(&mut self, x: u16, y: u16, color: Color) {
         if Self::coord_is_inside(x, y) {
-            self.display.print_point_color_at(x, y, color);
+            let fg = self.draw_buffer();
+            Self::write_pixel(fg, x, y, color.to_argb1555());
+            self.changed(fg, Rect::new(x as i32, y as i32, 1, 1));
         }
     }
 
-    fn set_pixel_invisible(&mut self, x: u16, y: u16) {
-        self.render_pixel(x, y, 0x0000);
-    }
-
     pub fn cursor(&mut self, x: u16, y: u16) {
         self.remove_last_cursor();
-        let c = RGBColor::from_rgb(0, 0, 0);
-        for i in 0..13 {
-            self.render_pixel(x.wrapping_add(i), y, c);
-            self.render_pixel(x.wrapping_sub(i), y, c);
-            self.render_pixel(x, y.wrapping_add(i), c);
-            self.render_pixel(x, y.wrapping_sub(i), c);
+        for rect in &Self::cursor_rects(x, y) {
+            self.fill_rect(Layer::Foreground, *rect, color::BLACK);
         }
         self.last_touch = (x, y);
     }
 
     pub fn remove_last_cursor(&mut self) {
-        let x = self.last_touch.0;
-        let y = self.last_touch.1;
-        for i in 0..13 {
-            self.set_pixel_invisible(x.wrapping_add(i), y);
-            self.set_pixel_invisible(x.wrapping_sub(i), y);
-            self.set_pixel_invisible(x, y.wrapping_add(i));
-            self.set_pixel_invisible(x, y.wrapping_sub(i));
+        let (x, y) = self.last_touch;
+        for rect in &Self::cursor_rects(x, y) {
+            self.clear_rect(*rect);
         }
     }
 
-    pub fn draw(&mut self, x: u16, y: u16, width: u16, img: &[u8]) {
-        for i in 0..(img.len() / 2) {
-            let img_idx = i * 2;
-            let dsp_y = y + (i as u16 / width);
-            let dsp_x = x + (i as u16 % width);
-            let p = ((img[img_idx] as u16) << 8) | (img[img_idx + 1] as u16);
-            self.render_pixel(dsp_x, dsp_y, p);
-        }
+    fn cursor_rects(x: u16, y: u16) -> [Rect; 2] {
+        let (x, y) = (x as i32, y as i32);
+        [Rect::new(x - 12, y, 25, 1), Rect::new(x, y - 12, 1, 25)]
+    }
+
+    // the position may be negative or beyond the display, only the visible
+    // part is drawn
+    pub fn draw(&mut self, x: i32, y: i32, width: u16, img: &[u8]) {
+        let fg = self.draw_buffer();
+        let size = (width, (img.len() / 2 / width as usize) as u16);
+        self.blit(fg,
+                  x,
+                  y,
+                  size,
+                  Self::display_rect(),
+                  |i| ((img[i * 2] as u16) << 8) | (img[i * 2 + 1] as u16));
     }
 
     pub fn draw_u16(&mut self, x: u16, y: u16, width: u16, img: &[u16]) {
-        for (i, px) in img.iter().enumerate() {
-            let dsp_y = y + (i as u16 / width);
-            let dsp_x = x + (i as u16 % width);
-            self.render_pixel(dsp_x, dsp_y, *px);
-        }
+        let fg = self.draw_buffer();
+        let size = (width, (img.len() / width as usize) as u16);
+        self.blit(fg, x as i32, y as i32, size, Self::display_rect(), |i| img[i]);
     }
 
     pub fn draw_u16_tuple(&mut self, img: &[(u16, u16, u16)]) {
         for px in img.iter() {
-            self.render_pixel(px.0, px.1, px.2);
+            if Self::coord_is_inside(px.0, px.1) {
+                let fg = self.draw_buffer();
+                Self::write_pixel(fg, px.0, px.1, px.2);
+                self.changed(fg, Rect::new(px.0 as i32, px.1 as i32, 1, 1));
+            }
         }
     }
 
-    pub fn draw_full_bg_unicolor(&mut self, color: u16) {
-        for y in 0..272 {
-            for x in 0..480 {
-                self.render_bg(x, y, color);
+    pub fn draw_full_bg_unicolor(&mut self, color: Color) {
+        self.draw_bg_unicolor(0, 0, constants::DISPLAY_SIZE.0, constants::DISPLAY_SIZE.1, color);
+    }
+
+    pub fn draw_bg(&mut self, x: u16, y: u16, width: u16, img: &[u8]) {
+        let size = (width, (img.len() / 2 / width as usize) as u16);
+        let addr = self.layer_addr(Layer::Background);
+        self.blit(addr,
+                  x as i32,
+                  y as i32,
+                  size,
+                  Self::display_rect(),
+                  |i| ((img[i * 2] as u16) << 8) | (img[i * 2 + 1] as u16));
+    }
+
+    pub fn draw_bg_u16(&mut self, x: u16, y: u16, width: u16, img: &[u16]) {
+        let size = (width, (img.len() / width as usize) as u16);
+        let addr = self.layer_addr(Layer::Background);
+        self.blit(addr, x as i32, y as i32, size, Self::display_rect(), |i| img[i]);
+    }
+
+    pub fn draw_bg_unicolor(&mut self, x: u16, y: u16, width: u16, height: u16, color: Color) {
+        let addr = self.layer_addr(Layer::Background);
+        self.fill(addr,
+                  Rect::new(x as i32, y as i32, width, height),
+                  color.to_argb1555());
+    }
+
+    pub fn draw_dump(&mut self, x: i32, y: i32, size: (u16, u16), dump: &[u8]) {
+        let fg = self.draw_buffer();
+        self.blit_dump(fg, x, y, size, dump, Self::display_rect(), animation::FULL_COVERAGE);
+    }
+
+    // draws only the part of the dump that lies within `clip`, the position
+    // may be negative or beyond the display
+    pub fn draw_dump_clipped(&mut self,
+                             x: i32,
+                             y: i32,
+                             size: (u16, u16),
+                             dump: &[u8],
+                             clip: Rect) {
+        let fg = self.draw_buffer();
+        self.blit_dump(fg, x, y, size, dump, clip, animation::FULL_COVERAGE);
+    }
+
+    // draws the dump scaled and rotated, (x, y) is the top left corner of the
+    // bounding box of the result
+    pub fn draw_dump_transformed(&mut self,
+                                 layer: Layer,
+                                 x: i32,
+                                 y: i32,
+                                 size: (u16, u16),
+                                 dump: &[u8],
+                                 transform: &Transform) {
+        let addr = self.layer_addr(layer);
+        self.blit_transformed(addr,
+                              x,
+                              y,
+                              size,
+                              dump,
+                              transform,
+                              Self::display_rect(),
+                              animation::FULL_COVERAGE);
+    }
+
+    pub fn fill_rect(&mut self, layer: Layer, rect: Rect, color: Color) {
+        let addr = self.layer_addr(layer);
+        self.fill(addr, rect, color.to_argb1555());
+    }
+
+    pub fn draw_line(&mut self, layer: Layer, from: (i32, i32), to: (i32, i32), color: Color) {
+        let addr = self.layer_addr(layer);
+        let c = color.to_argb1555();
+        primitives::line(from, to, |x, y| self.plot(addr, x, y, c));
+    }
+
+    pub fn draw_rect(&mut self, layer: Layer, rect: Rect, color: Color) {
+        let addr = self.layer_addr(layer);
+        let c = color.to_argb1555();
+        primitives::rect(rect, |x, y| self.plot(addr, x, y, c));
+    }
+
+    pub fn draw_rounded_rect(&mut self, layer: Layer, rect: Rect, radius: i32, color: Color) {
+        let addr = self.layer_addr(layer);
+        let c = color.to_argb1555();
+        primitives::rounded_rect(rect, radius, |x, y| self.plot(addr, x, y, c));
+    }
+
+    pub fn fill_rounded_rect(&mut self, layer: Layer, rect: Rect, radius: i32, color: Color) {
+        let addr = self.layer_addr(layer);
+        let c = color.to_argb1555();
+        primitives::filled_rounded_rect(rect, radius, |x0, x1, y| self.span(addr, x0, x1, y, c));
+    }
+
+    pub fn draw_circle(&mut self, layer: Layer, center: (i32, i32), radius: i32, color: Color) {
+        let addr = self.layer_addr(layer);
+        let c = color.to_argb1555();
+        primitives::circle(center, radius, |x, y| self.plot(addr, x, y, c));
+    }
+
+    pub fn fill_circle(&mut self, layer: Layer, center: (i32, i32), radius: i32, color: Color) {
+        let addr = self.layer_addr(layer);
+        let c = color.to_argb1555();
+        primitives::filled_circle(center, radius, |x0, x1, y| self.span(addr, x0, x1, y, c));
+    }
+
+    pub fn draw_polygon(&mut self, layer: Layer, points: &[(i32, i32)], color: Color) {
+        let addr = self.layer_addr(layer);
+        let c = color.to_argb1555();
+        primitives::polygon(points, |x, y| self.plot(addr, x, y, c));
+    }
+
+    pub fn fill_polygon(&mut self, layer: Layer, points: &[(i32, i32)], color: Color) {
+        let addr = self.layer_addr(layer);
+        let c = color.to_argb1555();
+        primitives::filled_polygon(points, |x0, x1, y| self.span(addr, x0, x1, y, c));
+    }
+
+    // draws a single line of text and returns the area it covers; `pos` is
+    // the top left, top center or top right corner depending on the alignment
+    pub fn draw_text(&mut self,
+                     layer: Layer,
+                     text: &str,
+                     pos: (i32, i32),
+                     style: &TextStyle)
+                     -> Rect {
+        let addr = self.layer_addr(layer);
+        self.blit_text(addr,
+                       text,
+                       pos,
+                       style,
+                       Self::display_rect(),
+                       animation::FULL_COVERAGE)
+    }
+
+    // wraps the text to the width of `area` and aligns every line within it,
+    // lines that do not fit below each other are dropped
+    pub fn draw_text_box(&mut self,
+                         layer: Layer,
+                         text: &str,
+                         area: Rect,
+                         style: &TextStyle)
+                         -> Rect {
+        let anchor_x = match style.align {
+            Align::Left => area.x,
+            Align::Center => area.x + area.width as i32 / 2,
+            Align::Right => area.right(),
+        };
+        let glyph_height = (style.font.height() * style.scale) as i32;
+        let mut y = area.y;
+        for line in font::wrap(text, style, area.width) {
+            if y + glyph_height > area.bottom() {
+                break;
             }
+            self.draw_text(layer, line, (anchor_x, y), style);
+            y += style.line_height() as i32;
         }
+        Rect::new(area.x, area.y, area.width, (y - area.y) as u16)
     }
 
-    pub fn draw_bg(&mut self, x: u16, y: u16, width: u16, img: &[u8]) {
-        for i in 0..(img.len() / 2) {
-            let img_idx = i * 2;
-            let dsp_y = y + (i as u16 / width);
-            let dsp_x = x + (i as u16 % width);
-            let p = ((img[img_idx] as u16) << 8) | (img[img_idx + 1] as u16);
-            self.render_bg(dsp_x, dsp_y, p);
+    fn layer_addr(&mut self, layer: Layer) -> u32 {
+        match layer {
+            Layer::Background => BG_ADDR,
+            Layer::Foreground => self.draw_buffer(),
         }
     }
 
-    pub fn draw_bg_u16(&mut self, x: u16, y: u16, width: u16, img: &[u16]) {
-        for (i, px) in img.iter().enumerate() {
-            let dsp_y = y + (i as u16 / width);
-            let dsp_x = x + (i as u16 % width);
-            self.render_bg(dsp_x, dsp_y, *px);
+    fn plot(&mut self, layer: u32, x: i32, y: i32, color: u16) {
+        if Self::display_rect().contains(x, y) {
+            Self::write_pixel(layer, x as u16, y as u16, color);
+            self.changed(layer, Rect::new(x, y, 1, 1));
+        }
+    }
+
+    fn span(&mut self, layer: u32, x_start: i32, x_end: i32, y: i32, color: u16) {
+        // clipped before the width is narrowed to u16
+        let x_start = cmp::max(x_start, 0);
+        let x_end = cmp::min(x_end, constants::DISPLAY_SIZE.0 as i32 - 1);
+        if x_end >= x_start {
+            self.fill(layer, Rect::new(x_start, y, (x_end - x_start + 1) as u16, 1), color);
         }
     }
 
-    pub fn draw_bg_unicolor(&mut self, x: u16, y: u16, width: u16, height: u16, color: u16) {
-        for dsp_y in y..y + height {
-            for dsp_x in x..x + width {
-                self.render_bg(dsp_x, dsp_y, color);
+    pub fn clear(&mut self, x: u16, y: u16, size: (u16, u16)) {
+        self.clear_rect(Rect::new(x as i32, y as i32, size.0, size.1));
+    }
+
+    pub fn clear_rect(&mut self, rect: Rect) {
+        self.fill_rect(Layer::Foreground, rect, color::TRANSPARENT);
+    }
+
+    pub fn draw_dump_bg(&mut self, x: i32, y: i32, size: (u16, u16), dump: &[u8]) {
+        let addr = self.layer_addr(Layer::Background);
+        self.blit_dump(addr, x, y, size, dump, Self::display_rect(), animation::FULL_COVERAGE);
+    }
+
+    // adds a sprite to the retained scene, it shows up with the next `compose`
+    pub fn add_sprite(&mut self,
+                      x: i32,
+                      y: i32,
+                      size: (u16, u16),
+                      img: &'static [u8])
+                      -> SpriteId {
+        let sprite = Sprite {
+            x: x,
+            y: y,
+            size: size,
+            img: img,
+            animation: None,
+            frame: animation::STILL,
+            remove_when_done: false,
+            transform: None,
+        };
+        self.dirty.push(sprite.rect());
+        match self.sprites.iter().position(|s| s.is_none()) {
+            Some(id) => {
+                self.sprites[id] = Some(sprite);
+                id
+            }
+            None => {
+                self.sprites.push(Some(sprite));
+                self.sprites.len() - 1
             }
         }
     }
 
-    pub fn draw_dump(&mut self, x: u16, y: u16, size: (u16, u16), dump: &[u8]) {
-        use renderer::RGBColor;
+    pub fn remove_sprite(&mut self, id: SpriteId) {
+        if let Some(sprite) = self.sprites[id].take() {
+            self.dirty.push(sprite.rect());
+        }
+    }
 
-        let img_cnt = size.0 as usize * size.1 as usize;
-        for i in 0..img_cnt {
-            let idx = i * 4;
-            let dsp_y = y + (i / size.0 as usize) as u16;
-            let dsp_x = x + (i % size.0 as usize) as u16;
-            let c = RGBColor::from_rgb_with_alpha(dump[idx + 3],
-                                                  dump[idx],
-                                                  dump[idx + 1],
-                                                  dump[idx + 2]);
-            self.render_pixel(dsp_x, dsp_y, c);
+    pub fn move_sprite(&mut self, id: SpriteId, x: i32, y: i32) {
+        if let Some(ref mut sprite) = self.sprites[id] {
+            self.dirty.push(sprite.rect());
+            sprite.x = x;
+            sprite.y = y;
+            self.dirty.push(sprite.rect());
         }
     }
 
-    pub fn clear(&mut self, x: u16, y: u16, size: (u16, u16)) {
-        let img_cnt = size.0 as usize * size.1 as usize;
-        for i in 0..img_cnt {
-            let dsp_y = y + (i / size.0 as usize) as u16;
-            let dsp_x = x + (i % size.0 as usize) as u16;
-            self.set_pixel_invisible(dsp_x, dsp_y);
+    pub fn set_sprite_img(&mut self, id: SpriteId, img: &'static [u8]) {
+        if let Some(ref mut sprite) = self.sprites[id] {
+            sprite.img = img;
+            self.dirty.push(sprite.rect());
         }
     }
 
-    pub fn draw_dump_bg(&mut self, x: u16, y: u16, size: (u16, u16), dump: &[u8]) {
-        use renderer::RGBColor;
+    // scales and rotates the sprite, its rectangle grows to the bounding box
+    // of the transformed image
+    pub fn set_sprite_transform(&mut self, id: SpriteId, transform: Option<Transform>) {
+        if let Some(ref mut sprite) = self.sprites[id] {
+            self.dirty.push(sprite.rect());
+            sprite.transform = transform;
+            self.dirty.push(sprite.rect());
+        }
+    }
 
-        let img_cnt = size.0 as usize * size.1 as usize;
-        for i in 0..img_cnt {
-            let idx = i * 4;
-            let dsp_y = y + (i / size.0 as usize) as u16;
-            let dsp_x = x + (i % size.0 as usize) as u16;
-            let c = RGBColor::from_rgb_with_alpha(dump[idx + 3],
-                                                  dump[idx],
-                                                  dump[idx + 1],
-                                                  dump[idx + 2]);
-            self.render_bg(dsp_x, dsp_y, c)
+    pub fn animate_sprite(&mut self, id: SpriteId, animation: &'static Animation, tick: usize) {
+        if let Some(ref mut sprite) = self.sprites[id] {
+            sprite.animation = Some(AnimationState::new(animation, tick));
+            sprite.remove_when_done = false;
         }
     }
 
-    pub fn get_random_pos(rng: &mut random::MTRng32, width: u16, height: u16) -> (u16, u16) {
-        (cmp::min(rng.rand() as u16 % constants::DISPLAY_SIZE.0,
-                  constants::DISPLAY_SIZE.0 - width - 1),
-         cmp::min(rng.rand() as u16 % constants::DISPLAY_SIZE.1,
-                  constants::DISPLAY_SIZE.1 - height - 1))
+    // plays the animation and removes the sprite afterwards
+    pub fn remove_sprite_animated(&mut self,
+                                  id: SpriteId,
+                                  animation: &'static Animation,
+                                  tick: usize) {
+        self.animate_sprite(id, animation, tick);
+        if let Some(ref mut sprite) = self.sprites[id] {
+            sprite.remove_when_done = true;
+        }
+    }
+
+    // bursts confetti out of `pos`, they are drawn on top of all sprites
+    pub fn emit_particles(&mut self,
+                          pos: (i32, i32),
+                          count: usize,
+                          tick: usize,
+                          rng: &mut random::MTRng32) {
+        self.particles.emit(pos, count, tick, rng);
+    }
+
+    // shows a text like "+50" at `pos` (top center) that rises and fades out,
+    // drawn on top of everything else in the scene
+    pub fn show_popup(&mut self, text: &str, pos: (i32, i32), color: Color, tick: usize) {
+        if let Some(rect) = self.popups.show(text, pos, color, tick) {
+            self.dirty.push(rect);
+        }
+    }
+
+    // advances all sprite animations, particles and popups to `tick` of the
+    // game clock
+    pub fn animate(&mut self, tick: usize) {
+        if let Some(rect) = self.popups.update(tick) {
+            self.dirty.push(rect);
+        }
+        if let Some(ref mut t) = self.transition {
+            t.update(tick);
+        }
+        if let Some(rect) = self.particles.update(tick) {
+            self.dirty.push(rect);
+        }
+        for id in 0..self.sprites.len() {
+            let mut remove = false;
+            if let Some(ref mut sprite) = self.sprites[id] {
+                let frame = match sprite.animation {
+                    Some(ref mut state) => state.update(tick),
+                    None => continue,
+                };
+                let frame = match frame {
+                    Some(frame) => frame,
+                    None => {
+                        sprite.animation = None;
+                        remove = sprite.remove_when_done;
+                        animation::STILL
+                    }
+                };
+                if frame != sprite.frame {
+                    sprite.frame = frame;
+                    self.dirty.push(sprite.rect());
+                }
+            }
+            if remove {
+                self.remove_sprite(id);
+            }
+        }
     }
-}
 
-pub struct RGBColor();
+    // removes all sprites, particles and popups
+    pub fn clear_scene(&mut self) {
+        if let Some(rect) = self.popups.clear() {
+            self.dirty.push(rect);
+        }
+        for id in 0..self.sprites.len() {
+            self.remove_sprite(id);
+        }
+        if let Some(rect) = self.particles.clear() {
+            self.dirty.push(rect);
+        }
+    }
 
-impl RGBColor {
-    pub fn from_rgb(r: u8, g: u8, b: u8) -> u16 {
-        Self::from_rgb_with_alpha(42, r, g, b)
+    // `compose` never touches reserved areas, so things drawn directly, like
+    // the hud, survive sprites and particles passing by
+    pub fn reserve(&mut self, rect: Rect) {
+        self.reserved.push(rect);
     }
 
-    pub fn from_rgb_with_alpha(a: u8, r: u8, g: u8, b: u8) -> u16 {
-        let r_f = (r / 8) as u16;
-        let g_f = (g / 8) as u16;
-        let b_f = (b / 8) as u16;
-        let c: u16 = if a >= 42 { 1 << 15 } else { 0 };
-        c | (r_f << 10) | (g_f << 5) | b_f
+    pub fn mark_dirty(&mut self, rect: Rect) {
+        self.dirty.push(rect);
     }
 
-    pub fn from_hex_with_alpha(color: u32) -> u16 {
-        let a = (color >> 24) as u8;
-        let r = (color >> 16) as u8;
-        let g = (color >> 8) as u8;
-        let b = color as u8;
-        Self::from_rgb_with_alpha(a, r, g, b)
+    // redraws all dirty regions of the foreground from the retained scene
+    pub fn compose(&mut self) {
+        self.split_dirty_rects();
+        let mut dirty = mem::replace(&mut self.dirty, Vec::new());
+        for reserved in &self.reserved {
+            let mut remaining = Vec::new();
+            for rect in &dirty {
+                remaining.extend(rect.subtract(reserved));
+            }
+            dirty = remaining;
+        }
+        let fg = self.draw_buffer();
+        let mut particles: Vec<(Rect, u16, u8)> = Vec::new();
+        if !dirty.is_empty() {
+            self.particles
+                .for_each(|r, c, coverage| particles.push((r, c.to_argb1555(), coverage)));
+        }
+        for rect in &dirty {
+            self.clear_rect(*rect);
+            for i in 0..self.sprites.len() {
+                if let Some(s) = self.sprites[i] {
+                    // animated sprites never leave their rectangle
+                    if let Some(clip) = s.rect().intersection(rect) {
+                        let x = s.x + s.frame.offset.0;
+                        let y = s.y + s.frame.offset.1;
+                        match s.transform {
+                            Some(ref t) => {
+                                self.blit_transformed(fg,
+                                                      x,
+                                                      y,
+                                                      s.size,
+                                                      s.img,
+                                                      t,
+                                                      clip,
+                                                      s.frame.coverage)
+                            }
+                            None => {
+                                self.blit_dump(fg, x, y, s.size, s.img, clip, s.frame.coverage)
+                            }
+                        }
+                    }
+                }
+            }
+            for &(r, c, coverage) in &particles {
+                if let Some(visible) = r.intersection(rect) {
+                    self.fill_dithered(fg, visible, c, coverage);
+                }
+            }
+        }
+        if dirty.is_empty() {
+            return;
+        }
+        // drawing needs `self`, so the popups are borrowed out meanwhile
+        let popups = mem::replace(&mut self.popups, PopupSystem::new());
+        popups.for_each(|text, pos, style, coverage| for rect in &dirty {
+            self.blit_text(fg, text, pos, style, *rect, coverage);
+        });
+        self.popups = popups;
     }
 
-    pub fn from_hex(color: u32) -> u16 {
-        let r = (color >> 16) as u8;
-        let g = (color >> 8) as u8;
-        let b = color as u8;
-        Self::from_rgb(r, g, b)
+    // splits overlapping dirty rectangles into disjoint parts, so no pixel
+    // is redrawn twice and nothing outside the dirty areas is cleared
+    fn split_dirty_rects(&mut self) {
+        let dirty = mem::replace(&mut self.dirty, Vec::new());
+        for rect in dirty {
+            let mut parts = Vec::new();
+            parts.push(rect);
+            for done in &self.dirty {
+                let mut remaining = Vec::new();
+                for part in &parts {
+                    remaining.extend(part.subtract(done));
+                }
+                parts = remaining;
+            }
+            self.dirty.extend(parts);
+        }
+    }
+
+    fn display_rect() -> Rect {
+        Rect::new(0, 0, constants::DISPLAY_SIZE.0, constants::DISPLAY_SIZE.1)
+    }
+
+    // the part of an image at (x, y) that ends up on the display, so the
+    // blitting loops do not need to check every single pixel
+    fn visible_part(x: i32, y: i32, size: (u16, u16), clip: Rect) -> Option<Rect> {
+        Rect::new(x, y, size.0, size.1)
+            .intersection(&clip)
+            .and_then(|r| r.intersection(&Self::display_rect()))
+    }
+
+    fn fill(&mut self, layer: u32, rect: Rect, color: u16) {
+        let visible = match rect.intersection(&Self::display_rect()) {
+            Some(r) => r,
+            None => return,
+        };
+        self.changed(layer, visible);
+        if let Some(ref mut dma2d) = self.dma2d {
+            let dst = layer + ((visible.y as u32) * 480 + (visible.x as u32)) * 2;
+            dma2d.fill(dst,
+                       constants::DISPLAY_SIZE.0 - visible.width,
+                       (visible.width, visible.height),
+                       color);
+            return;
+        }
+        for dsp_y in visible.y..visible.bottom() {
+            for dsp_x in visible.x..visible.right() {
+                Self::write_pixel(layer, dsp_x as u16, dsp_y as u16, color);
+            }
+        }
+    }
+
+    // only fills the pixels the dither pattern of `coverage` covers, the
+    // others keep what is below
+    fn fill_dithered(&mut self, layer: u32, rect: Rect, color: u16, coverage: u8) {
+        if coverage >= animation::FULL_COVERAGE {
+            self.fill(layer, rect, color);
+            return;
+        }
+        let visible = match rect.intersection(&Self::display_rect()) {
+            Some(r) => r,
+            None => return,
+        };
+        self.changed(layer, visible);
+        for dsp_y in visible.y..visible.bottom() {
+            for dsp_x in visible.x..visible.right() {
+                if animation::is_covered(dsp_x, dsp_y, coverage) {
+                    Self::write_pixel(layer, dsp_x as u16, dsp_y as u16, color);
+                }
+            }
+        }
+    }
+
+    // draws the visible part of a `size` image, `pixel` maps the index of an
+    // image pixel to its color
+    fn blit<F>(&mut self, layer: u32, x: i32, y: i32, size: (u16, u16), clip: Rect, pixel: F)
+        where F: Fn(usize) -> u16
+    {
+        let visible = match Self::visible_part(x, y, size, clip) {
+            Some(r) => r,
+            None => return,
+        };
+        self.changed(layer, visible);
+        let first_px = (visible.y - y) as usize * size.0 as usize + (visible.x - x) as usize;
+        for row in 0..visible.height as usize {
+            for col in 0..visible.width as usize {
+                let c = pixel(first_px + row * size.0 as usize + col);
+                Self::write_pixel(layer,
+                                  (visible.x + col as i32) as u16,
+                                  (visible.y + row as i32) as u16,
+                                  c);
+            }
+        }
+    }
+
+    fn blit_dump(&mut self,
+                 layer: u32,
+                 x: i32,
+                 y: i32,
+                 size: (u16, u16),
+                 dump: &[u8],
+                 clip: Rect,
+                 coverage: u8) {
+        let full = coverage >= animation::FULL_COVERAGE;
+        if full && self.dma2d.is_some() && self.dump_is_opaque(dump) {
+            let visible = match Self::visible_part(x, y, size, clip) {
+                Some(r) => r,
+                None => return,
+            };
+            self.changed(layer, visible);
+            let first_px = (visible.y - y) as usize * size.0 as usize + (visible.x - x) as usize;
+            let dst = layer + ((visible.y as u32) * 480 + (visible.x as u32)) * 2;
+            if let Some(ref mut dma2d) = self.dma2d {
+                dma2d.convert(dump[first_px * 4..].as_ptr() as u32,
+                              PixelFormat::Argb8888,
+                              size.0 - visible.width,
+                              Some(0xFF),
+                              dst,
+                              constants::DISPLAY_SIZE.0 - visible.width,
+                              (visible.width, visible.height));
+            }
+            return;
+        }
+        let width = size.0 as usize;
+        self.blit(layer, x, y, size, clip, |i| {
+            if !full && !animation::is_covered((i % width) as i32, (i / width) as i32, coverage) {
+                return 0x0000;
+            }
+            let idx = i * 4;
+            Color::argb(dump[idx + 3], dump[idx + 2], dump[idx + 1], dump[idx]).to_argb1555()
+        });
+    }
+
+    // `coverage` dithers whole font pixels, so scaled text fades in blocks
+    fn blit_text(&mut self,
+                 layer: u32,
+                 text: &str,
+                 pos: (i32, i32),
+                 style: &TextStyle,
+                 clip: Rect,
+                 coverage: u8)
+                 -> Rect {
+        let width = style.text_width(text);
+        let left = match style.align {
+            Align::Left => pos.0,
+            Align::Center => pos.0 - width as i32 / 2,
+            Align::Right => pos.0 - width as i32,
+        };
+        let c = style.color.to_argb1555();
+        let scale = style.scale as i32;
+        let mut x = left;
+        for ch in text.chars() {
+            for (col, bits) in style.font.glyph(ch).iter().enumerate() {
+                for row in 0..style.font.height() {
+                    if bits & (1 << row) == 0 {
+                        continue;
+                    }
+                    let px = x + col as i32 * scale;
+                    let py = pos.1 + row as i32 * scale;
+                    if !animation::is_covered(px / scale, py / scale, coverage) {
+                        continue;
+                    }
+                    let pixel = Rect::new(px, py, style.scale, style.scale);
+                    if let Some(visible) = pixel.intersection(&clip) {
+                        if scale == 1 {
+                            self.plot(layer, px, py, c);
+                        } else {
+                            self.fill(layer, visible, c);
+                        }
+                    }
+                }
+            }
+            x += (style.font.char_width(ch) + style.font.spacing()) as i32 * scale;
+        }
+        Rect::new(left, pos.1, width, style.font.height() * style.scale)
+    }
+
+    fn blit_transformed(&mut self,
+                        layer: u32,
+                        x: i32,
+                        y: i32,
+                        size: (u16, u16),
+                        dump: &[u8],
+                        transform: &Transform,
+                        clip: Rect,
+                        coverage: u8) {
+        let out = transform.output_size();
+        let width = out.0 as usize;
+        self.blit(layer, x, y, out, clip, |i| {
+            let (px, py) = ((i % width) as i32, (i / width) as i32);
+            if !animation::is_covered(px, py, coverage) {
+                return 0x0000;
+            }
+            match transform.sample(dump, size, px, py) {
+                Some(c) => c.to_argb1555(),
+                None => 0x0000,
+            }
+        });
+    }
+
+    // the dma2d converts alpha with a threshold of 128 instead of our 42, so
+    // only dumps without a real alpha channel are handed to it. every pixel
+    // is checked the first time a dump is drawn.
+    fn dump_is_opaque(&mut self, dump: &[u8]) -> bool {
+        let key = (dump.as_ptr() as usize, dump.len());
+        if let Some(&(_, _, opaque)) = self.opaque_dumps
+               .iter()
+               .find(|d| (d.0, d.1) == key) {
+            return opaque;
+        }
+        let opaque = dump.len() >= 4 &&
+                     dump.chunks(4).all(|px| px.len() == 4 && px[3] == OPAQUE_DUMP_ALPHA);
+        self.opaque_dumps.push((key.0, key.1, opaque));
+        opaque
+    }
+
+    pub fn get_random_pos(rng: &mut random::MTRng32, width: u16, height: u16) -> (u16, u16) {
+        (cmp::min(rng.rand() as u16 % constants::DISPLAY_SIZE.0,
+                  constants::DISPLAY_SIZE.0 - width - 1),
+         cmp::min(rng.rand() as u16 % constants::DISPLAY_SIZE.1,
+                  constants::DISPLAY_SIZE.1 - height - 1))
     }
 }
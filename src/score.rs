@@ -0,0 +1,49 @@
+// scores saturate instead of overflowing, the displays show all nines
+// once they run out of digits
+pub type Score = i32;
+
+// what penalties do to a score that would drop below zero
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum ScorePolicy {
+    ClampAtZero,
+    AllowNegative,
+}
+
+// `score` after gaining `points`, which are negative for penalties
+pub fn add(score: Score, points: Score, policy: ScorePolicy) -> Score {
+    let sum = score.saturating_add(points);
+    if policy == ScorePolicy::ClampAtZero && sum < 0 {
+        0
+    } else {
+        sum
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn saturates_at_the_limits() {
+        assert_eq!(add(Score::max_value() - 10, 50, ScorePolicy::AllowNegative),
+                   Score::max_value());
+        assert_eq!(add(Score::min_value() + 10, -50, ScorePolicy::AllowNegative),
+                   Score::min_value());
+        assert_eq!(add(Score::max_value(), -50, ScorePolicy::ClampAtZero),
+                   Score::max_value() - 50);
+    }
+
+    #[test]
+    fn clamps_at_zero() {
+        assert_eq!(add(30, -50, ScorePolicy::ClampAtZero), 0);
+        assert_eq!(add(0, -50, ScorePolicy::ClampAtZero), 0);
+        assert_eq!(add(80, -50, ScorePolicy::ClampAtZero), 30);
+        assert_eq!(add(Score::min_value() + 10, -50, ScorePolicy::ClampAtZero), 0);
+    }
+
+    #[test]
+    fn allows_negative() {
+        assert_eq!(add(30, -50, ScorePolicy::AllowNegative), -20);
+        assert_eq!(add(-20, 70, ScorePolicy::AllowNegative), 50);
+    }
+}
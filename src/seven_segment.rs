@@ -1,115 +1,389 @@
+use core::cmp;
 use collections::vec::Vec;
-use renderer::Renderer;
+use renderer::{Layer, Renderer};
+use geometry::Rect;
 use constants;
+use color::{self, Color};
 
-struct Segment {
-    pixel: Vec<(u16, u16)>,
+// the display has 14 segments: a to f around the element, the middle bar
+// split in g1 and g2, diagonals h, j, k, m and center bars i and l.
+// digits only use the classic seven of them.
+//
+//    aaaa
+//   fh i jb
+//   f hij b
+//    g1 g2
+//   e kl m c
+//   ek l  mc
+//    dddd  p
+//
+// the decimal point p and the two colon dots sit in the gap right of the
+// element, the four corner dots fill the edges of the frame for the "H" of
+// the highscore.
+const SEGMENT_COUNT: usize = 21;
+const SEG_A: u32 = 1 << 0;
+const SEG_B: u32 = 1 << 1;
+const SEG_C: u32 = 1 << 2;
+const SEG_D: u32 = 1 << 3;
+const SEG_E: u32 = 1 << 4;
+const SEG_F: u32 = 1 << 5;
+const SEG_G1: u32 = 1 << 6;
+const SEG_G2: u32 = 1 << 7;
+const SEG_H: u32 = 1 << 8;
+const SEG_I: u32 = 1 << 9;
+const SEG_J: u32 = 1 << 10;
+const SEG_K: u32 = 1 << 11;
+const SEG_L: u32 = 1 << 12;
+const SEG_M: u32 = 1 << 13;
+const SEG_DP: u32 = 1 << 14;
+const SEG_COLON: u32 = (1 << 15) | (1 << 16);
+const SEG_CORNERS: u32 = 0b1111 << 17;
+const SEG_G: u32 = SEG_G1 | SEG_G2;
+
+const DIGITS: [u32; 10] = [SEG_A | SEG_B | SEG_C | SEG_D | SEG_E | SEG_F,
+                           SEG_B | SEG_C,
+                           SEG_A | SEG_B | SEG_D | SEG_E | SEG_G,
+                           SEG_A | SEG_B | SEG_C | SEG_D | SEG_G,
+                           SEG_B | SEG_C | SEG_F | SEG_G,
+                           SEG_A | SEG_C | SEG_D | SEG_F | SEG_G,
+                           SEG_A | SEG_C | SEG_D | SEG_E | SEG_F | SEG_G,
+                           SEG_A | SEG_B | SEG_C,
+                           SEG_A | SEG_B | SEG_C | SEG_D | SEG_E | SEG_F | SEG_G,
+                           SEG_A | SEG_B | SEG_C | SEG_D | SEG_F | SEG_G];
+
+// glyphs from ' ' to 'Z', lower case letters use the upper case ones and
+// unknown characters are left blank
+const FIRST_CHAR: char = ' ';
+const GLYPHS: [u32; 59] = [// ' ' ! " # $ % & '
+                           0,
+                           SEG_B | SEG_C,
+                           SEG_F | SEG_I,
+                           SEG_B | SEG_C | SEG_D | SEG_G | SEG_I | SEG_L,
+                           SEG_A | SEG_C | SEG_D | SEG_F | SEG_G | SEG_I | SEG_L,
+                           SEG_C | SEG_F | SEG_G | SEG_J | SEG_K,
+                           SEG_A | SEG_D | SEG_E | SEG_G1 | SEG_H | SEG_J | SEG_M,
+                           SEG_I,
+                           // ( ) * + , - . /
+                           SEG_J | SEG_M,
+                           SEG_H | SEG_K,
+                           SEG_G | SEG_H | SEG_I | SEG_J | SEG_K | SEG_L | SEG_M,
+                           SEG_G | SEG_I | SEG_L,
+                           SEG_K,
+                           SEG_G,
+                           SEG_D,
+                           SEG_J | SEG_K,
+                           // 0 to 9, the zero is slashed to tell it from the O
+                           SEG_A | SEG_B | SEG_C | SEG_D | SEG_E | SEG_F | SEG_J | SEG_K,
+                           SEG_B | SEG_C,
+                           SEG_A | SEG_B | SEG_D | SEG_E | SEG_G,
+                           SEG_A | SEG_B | SEG_C | SEG_D | SEG_G,
+                           SEG_B | SEG_C | SEG_F | SEG_G,
+                           SEG_A | SEG_C | SEG_D | SEG_F | SEG_G,
+                           SEG_A | SEG_C | SEG_D | SEG_E | SEG_F | SEG_G,
+                           SEG_A | SEG_B | SEG_C,
+                           SEG_A | SEG_B | SEG_C | SEG_D | SEG_E | SEG_F | SEG_G,
+                           SEG_A | SEG_B | SEG_C | SEG_D | SEG_F | SEG_G,
+                           // : ; < = > ? @
+                           SEG_I | SEG_L,
+                           SEG_I | SEG_K,
+                           SEG_J | SEG_M,
+                           SEG_D | SEG_G,
+                           SEG_H | SEG_K,
+                           SEG_A | SEG_B | SEG_G2 | SEG_L,
+                           SEG_A | SEG_B | SEG_D | SEG_E | SEG_F | SEG_G2 | SEG_I,
+                           // A to M
+                           SEG_A | SEG_B | SEG_C | SEG_E | SEG_F | SEG_G,
+                           SEG_A | SEG_B | SEG_C | SEG_D | SEG_G2 | SEG_I | SEG_L,
+                           SEG_A | SEG_D | SEG_E | SEG_F,
+                           SEG_A | SEG_B | SEG_C | SEG_D | SEG_I | SEG_L,
+                           SEG_A | SEG_D | SEG_E | SEG_F | SEG_G1,
+                           SEG_A | SEG_E | SEG_F | SEG_G1,
+                           SEG_A | SEG_C | SEG_D | SEG_E | SEG_F | SEG_G2,
+                           SEG_B | SEG_C | SEG_E | SEG_F | SEG_G,
+                           SEG_A | SEG_D | SEG_I | SEG_L,
+                           SEG_B | SEG_C | SEG_D | SEG_E,
+                           SEG_E | SEG_F | SEG_G1 | SEG_J | SEG_M,
+                           SEG_D | SEG_E | SEG_F,
+                           SEG_B | SEG_C | SEG_E | SEG_F | SEG_H | SEG_J,
+                           // N to Z
+                           SEG_B | SEG_C | SEG_E | SEG_F | SEG_H | SEG_M,
+                           SEG_A | SEG_B | SEG_C | SEG_D | SEG_E | SEG_F,
+                           SEG_A | SEG_B | SEG_E | SEG_F | SEG_G,
+                           SEG_A | SEG_B | SEG_C | SEG_D | SEG_E | SEG_F | SEG_M,
+                           SEG_A | SEG_B | SEG_E | SEG_F | SEG_G | SEG_M,
+                           SEG_A | SEG_C | SEG_D | SEG_F | SEG_G,
+                           SEG_A | SEG_I | SEG_L,
+                           SEG_B | SEG_C | SEG_D | SEG_E | SEG_F,
+                           SEG_E | SEG_F | SEG_J | SEG_K,
+                           SEG_B | SEG_C | SEG_E | SEG_F | SEG_K | SEG_M,
+                           SEG_H | SEG_J | SEG_K | SEG_M,
+                           SEG_H | SEG_J | SEG_L,
+                           SEG_A | SEG_D | SEG_J | SEG_K];
+
+// straight bars are filled as rectangles, diagonals as parallelograms
+#[derive(Clone, Copy)]
+enum Shape {
+    Bar(Rect),
+    Slant([(i32, i32); 4]),
 }
 
-impl Segment {
-    pub fn new(offset: (u16, u16), size: (u16, u16)) -> Self {
-        let mut result: Vec<(u16, u16)> = Vec::new();
-        for x in 0..size.0 {
-            for y in 0..size.1 {
-                result.push((x + offset.0, y + offset.1));
+impl Shape {
+    fn bounds(&self) -> Rect {
+        match *self {
+            Shape::Bar(r) => r,
+            Shape::Slant(points) => {
+                let left = points.iter().map(|p| p.0).min().unwrap();
+                let right = points.iter().map(|p| p.0).max().unwrap();
+                let top = points.iter().map(|p| p.1).min().unwrap();
+                let bottom = points.iter().map(|p| p.1).max().unwrap();
+                Rect::new(left, top, (right - left + 1) as u16, (bottom - top + 1) as u16)
             }
         }
-
-        Segment { pixel: result }
     }
 }
 
+// where numbers with blanked leading zeros end up
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Align {
+    Left,
+    Right,
+}
+
 pub struct SSDisplay {
-    segs: [Segment; 7],
-    corners: [Segment; 4],
+    segs: [Shape; SEGMENT_COUNT],
     pos: (u16, u16),
+    digits: usize,
     elem_width: u16,
     gap: u16,
+    align: Align,
+    leading_zeros: bool,
+    // x offset, segment mask and color of every element drawn so far
+    shown: Vec<(u16, u32, Color)>,
 }
 
 impl SSDisplay {
-    // scales elemts in a factor of 2/1 (height/width) with the given width
-    pub fn new(pos: (u16, u16), elem_width: u16, gap: u16) -> Self {
+    // scales elemts in a factor of 2/1 (height/width) with the given width,
+    // numbers are shown with `digits` digits including leading zeros
+    pub fn new(pos: (u16, u16), digits: usize, elem_width: u16, gap: u16) -> Self {
         let seg_size = (elem_width / constants::HEIGHT_TO_WIDTH_SCALING,
                         elem_width / (constants::HEIGHT_TO_WIDTH_SCALING * 2));
+        let (len, thick) = seg_size;
+        let half = (len / 2, thick);
+        // width of the center bars and diagonals
+        let thin = cmp::max(thick / 2, 1);
+        let center = thick + (len - thin) / 2;
+        let (t, l, d) = (thick as i32, len as i32, thin as i32);
+        let (left, right, mid) = (t, t + l, t + l / 2);
+        let (upper, middle, lower, bottom) = (t, t + l, 2 * t + l, 2 * t + 2 * l);
+        let dot = cmp::max(cmp::min(thick, gap), 1);
+        let dot_x = elem_width + gap.saturating_sub(dot) / 2;
+        let corner = (thick, thick);
         SSDisplay {
-            segs: [// real seven segements
-                   Segment::new((seg_size.1, 0), seg_size),
-                   Segment::new((seg_size.1 + seg_size.0, seg_size.1), flip(seg_size)),
-                   Segment::new((seg_size.1 + seg_size.0, seg_size.0 * 2), flip(seg_size)),
-                   Segment::new((seg_size.1, seg_size.0 * 3), seg_size),
-                   Segment::new((0, seg_size.0 * 2), flip(seg_size)),
-                   Segment::new((0, seg_size.1), flip(seg_size)),
-                   Segment::new((seg_size.1, seg_size.0 + seg_size.1), seg_size)],
-            corners: [// corner points for pretty printing
-                      Segment::new((0, 0), (seg_size.1, seg_size.1)),
-                      Segment::new((seg_size.1 + seg_size.0, 0), (seg_size.1, seg_size.1)),
-                      Segment::new((seg_size.1 + seg_size.0, (seg_size.1 + seg_size.0) * 2),
-                                   (seg_size.1, seg_size.1)),
-                      Segment::new((0, (seg_size.1 + seg_size.0) * 2), (seg_size.1, seg_size.1))],
+            segs: [bar((thick, 0), seg_size),
+                   bar((thick + len, thick), flip(seg_size)),
+                   bar((thick + len, len * 2), flip(seg_size)),
+                   bar((thick, len * 3), seg_size),
+                   bar((0, len * 2), flip(seg_size)),
+                   bar((0, thick), flip(seg_size)),
+                   bar((thick, len + thick), half),
+                   bar((thick + half.0, len + thick), (len - half.0, thick)),
+                   Shape::Slant([(left, upper),
+                                 (left + d, upper),
+                                 (mid, middle),
+                                 (mid - d, middle)]),
+                   bar((center, thick), (thin, len)),
+                   Shape::Slant([(right - d, upper),
+                                 (right, upper),
+                                 (mid + d, middle),
+                                 (mid, middle)]),
+                   Shape::Slant([(mid - d, lower),
+                                 (mid, lower),
+                                 (left + d, bottom),
+                                 (left, bottom)]),
+                   bar((center, len * 2), (thin, len)),
+                   Shape::Slant([(mid, lower),
+                                 (mid + d, lower),
+                                 (right, bottom),
+                                 (right - d, bottom)]),
+                   bar((dot_x, len * 3 + thick - dot), (dot, dot)),
+                   bar((dot_x, thick + (len - dot) / 2), (dot, dot)),
+                   bar((dot_x, 2 * thick + len + (len - dot) / 2), (dot, dot)),
+                   bar((0, 0), corner),
+                   bar((thick + len, 0), corner),
+                   bar((thick + len, (thick + len) * 2), corner),
+                   bar((0, (thick + len) * 2), corner)],
             pos: pos,
+            digits: digits,
             elem_width: elem_width,
             gap: gap,
+            align: Align::Right,
+            leading_zeros: true,
+            shown: Vec::new(),
         }
     }
 
-    pub fn render(&self, n: u16, color: u16, rend: &mut Renderer) {
+    // blanks leading zeros and moves the remaining digits to `align`
+    pub fn without_leading_zeros(mut self, align: Align) -> Self {
+        self.leading_zeros = false;
+        self.align = align;
+        self
+    }
+
+    // numbers that do not fit are shown as the largest one that does
+    pub fn render(&mut self, n: u32, color: Color, rend: &mut Renderer) {
         self.render_offset(n, color, 0, rend);
     }
 
-    fn render_offset(&self, n: u16, color: u16, offset: u16, rend: &mut Renderer) {
-        let bcd = u16_to_bcd(n);
-        let mut offs = offset;
-        for i in (0..5).rev() {
-            let (print, alpha) = get_segment_indices(bcd[i]);
-            self.render_segments(&print, color, offs, rend);
-            self.render_segments(&alpha, 0x0000, offs, rend);
+    // negative numbers get a minus sign in front and one digit less
+    pub fn render_signed(&mut self, n: i32, color: Color, rend: &mut Renderer) {
+        if n >= 0 {
+            self.render(n as u32, color, rend);
+            return;
+        }
+        let segments = negative_segments(n, self.digits, self.leading_zeros);
+        self.render_slots(&segments, color, 0, rend);
+    }
 
+    fn render_offset(&mut self, n: u32, color: Color, offset: u16, rend: &mut Renderer) {
+        let min_digits = if self.leading_zeros { self.digits } else { 1 };
+        let segments = number_segments(n, min_digits, self.digits);
+        self.render_slots(&segments, color, offset, rend);
+    }
+
+    // shows `text` in the elements of the display, cut off if it is too long
+    pub fn render_text(&mut self, text: &str, color: Color, rend: &mut Renderer) {
+        let segments: Vec<u32> = text.chars().map(glyph_segments).collect();
+        self.render_slots(&segments, color, 0, rend);
+    }
+
+    // shows the remaining `millis` as minutes and seconds with a blinking
+    // colon from one minute on, as blinking tenths below `tenths_below` and
+    // as plain seconds otherwise. minutes need at least four digits.
+    pub fn render_time(&mut self,
+                       millis: u32,
+                       tenths_below: u32,
+                       color: Color,
+                       rend: &mut Renderer) {
+        // whole seconds are rounded up, so the time is over when it shows 0
+        let seconds = (millis + 999) / 1000;
+        let segments = if millis >= 60_000 && self.digits >= 4 {
+            let minutes = cmp::min(seconds / 60, 99);
+            let mut segments = number_segments(minutes, 2, 2);
+            segments.extend(number_segments(seconds % 60, 2, 2));
+            if millis % 1000 >= 500 {
+                segments[1] |= SEG_COLON;
+            }
+            segments
+        } else if millis < tenths_below && self.digits >= 2 {
+            let tenths = millis / 100;
+            let mut segments = number_segments(tenths / 10, 1, self.digits - 1);
+            let last = segments.len() - 1;
+            segments[last] |= SEG_DP;
+            segments.extend(number_segments(tenths % 10, 1, 1));
+            // off for the first quarter of every second counted down, the
+            // final 0.0 stays visible
+            if millis % 1000 >= 750 {
+                for s in &mut segments {
+                    *s = 0;
+                }
+            }
+            segments
+        } else {
+            number_segments(seconds, 1, self.digits)
+        };
+        self.render_slots(&segments, color, 0, rend);
+    }
+
+    // places the elements according to the alignment and blanks the rest
+    fn render_slots(&mut self, segments: &[u32], color: Color, offset: u16, rend: &mut Renderer) {
+        let len = cmp::min(segments.len(), self.digits);
+        let first = match self.align {
+            Align::Left => 0,
+            Align::Right => self.digits - len,
+        };
+        let mut offs = offset;
+        for slot in 0..self.digits {
+            let element = if slot >= first && slot < first + len {
+                segments[slot - first]
+            } else {
+                0
+            };
+            self.render_element(element, color, offs, rend);
             offs += self.elem_width + self.gap;
         }
     }
 
-    pub fn render_hs(&self, n: u16, color: u16, rend: &mut Renderer) {
-        let h = ([1, 2, 4, 5, 6], [0, 3]);
-        let s = ([0, 2, 3, 5, 6], [1, 4]);
-        let minus = ([6], [0, 1, 2, 3, 4, 5]);
-
+    pub fn render_hs(&mut self, n: u32, color: Color, rend: &mut Renderer) {
         let mut offset = 0;
-        self.render_segments(&h.0, color, offset, rend);
-        self.render_segments(&h.1, 0x0000, offset, rend);
-        self.render_corners(color, offset, rend);
+        self.render_element(glyph_segments('H') | SEG_CORNERS, color, offset, rend);
 
         offset += self.elem_width + self.gap;
-        self.render_segments(&s.0, color, offset, rend);
-        self.render_segments(&s.1, 0x0000, offset, rend);
+        self.render_element(glyph_segments('S'), color, offset, rend);
 
         offset += self.elem_width + constants::HS_SPACE_SIZE;
-        self.render_segments(&minus.0, color, offset, rend);
-        self.render_segments(&minus.1, 0x0000, offset, rend);
+        self.render_element(glyph_segments('-'), color, offset, rend);
 
         offset += self.elem_width + constants::HS_SPACE_SIZE;
         self.render_offset(n, color, offset, rend);
     }
 
-    fn render_segments(&self, segs: &[usize], color: u16, offset: u16, rend: &mut Renderer) {
-        for seg_num in segs {
-            let seg = &self.segs[*seg_num];
-            for p in &seg.pixel {
-                rend.render_pixel(p.0 + offset + self.pos.0, p.1 + self.pos.1, color);
+    // only fills the segments that look different from the last time this
+    // element was drawn; segments are cleared first and the diagonals overlap
+    // their neighbours, so lit segments near a cleared one are filled again
+    fn render_element(&mut self, segments: u32, color: Color, offset: u16, rend: &mut Renderer) {
+        let previous = self.shown.iter().position(|e| e.0 == offset).map(|p| self.shown[p]);
+        let changed = |i: usize| match previous {
+            Some((_, old_segments, old_color)) => {
+                segment_color(old_segments, old_color, i) != segment_color(segments, color, i)
             }
+            None => true,
+        };
+        let mut cleared: Vec<Rect> = Vec::new();
+        for i in 0..SEGMENT_COUNT {
+            if segments & (1 << i) == 0 && changed(i) {
+                self.draw_segment(i, offset, color::TRANSPARENT, rend);
+                cleared.push(self.segs[i].bounds());
+            }
+        }
+        for i in 0..SEGMENT_COUNT {
+            if segments & (1 << i) == 0 {
+                continue;
+            }
+            let bounds = self.segs[i].bounds();
+            if changed(i) || cleared.iter().any(|r| r.overlaps(&bounds)) {
+                self.draw_segment(i, offset, color, rend);
+            }
+        }
+        match self.shown.iter().position(|e| e.0 == offset) {
+            Some(p) => self.shown[p] = (offset, segments, color),
+            None => self.shown.push((offset, segments, color)),
         }
     }
 
-    fn render_corners(&self, color: u16, offset: u16, rend: &mut Renderer) {
-        for corner in &self.corners {
-            for p in &corner.pixel {
-                rend.render_pixel(p.0 + offset + self.pos.0, p.1 + self.pos.1, color);
+    fn draw_segment(&self, segment: usize, offset: u16, color: Color, rend: &mut Renderer) {
+        let (x, y) = ((self.pos.0 + offset) as i32, self.pos.1 as i32);
+        match self.segs[segment] {
+            Shape::Bar(r) => {
+                rend.fill_rect(Layer::Foreground,
+                               Rect::new(r.x + x, r.y + y, r.width, r.height),
+                               color)
+            }
+            Shape::Slant(points) => {
+                let mut moved = points;
+                for p in &mut moved {
+                    *p = (p.0 + x, p.1 + y);
+                }
+                rend.fill_polygon(Layer::Foreground, &moved, color);
             }
         }
     }
 
-    pub fn calculate_width(elem_width: u16, gap: u16) -> u16 {
-        5 * elem_width + 4 * gap
+    pub fn calculate_width(digits: usize, elem_width: u16, gap: u16) -> u16 {
+        if digits == 0 {
+            return 0;
+        }
+        let digits = digits as u16;
+        digits * elem_width + (digits - 1) * gap
     }
 
     pub fn calculate_height(elem_width: u16) -> u16 {
@@ -120,88 +394,93 @@ impl SSDisplay {
         3 * elem_width + gap + 2 * constants::HS_SPACE_SIZE
     }
 
-    pub fn calculate_hs_width(elem_width: u16, gap: u16) -> u16 {
-        Self::calculate_hs_prefix_width(elem_width, gap) + Self::calculate_width(elem_width, gap)
+    pub fn calculate_hs_width(digits: usize, elem_width: u16, gap: u16) -> u16 {
+        Self::calculate_hs_prefix_width(elem_width, gap) +
+        Self::calculate_width(digits, elem_width, gap)
     }
 }
 
+fn bar(offset: (u16, u16), size: (u16, u16)) -> Shape {
+    Shape::Bar(Rect::new(offset.0 as i32, offset.1 as i32, size.0, size.1))
+}
+
 fn flip(tuple: (u16, u16)) -> (u16, u16) {
     (tuple.1, tuple.0)
 }
 
-fn u16_to_bcd(n: u16) -> [u16; 5] {
+// segments of the decimal digits of `n`, most significant first; at least
+// `min_digits` long with leading zeros and all nines if it needs more than
+// `max_digits`
+fn number_segments(n: u32, min_digits: usize, max_digits: usize) -> Vec<u32> {
     let mut tmp = n;
-    let mut result: [u16; 5] = [0; 5];
-
-    result[4] = tmp / 10000;
-    tmp -= result[4] * 10000;
-    result[3] = tmp / 1000;
-    tmp -= result[3] * 1000;
-    result[2] = tmp / 100;
-    tmp -= result[2] * 100;
-    result[1] = tmp / 10;
-    tmp -= result[1] * 10;
-    result[0] = tmp;
-
+    let mut result: Vec<u32> = Vec::new();
+    while result.len() < max_digits && (tmp > 0 || result.len() < min_digits) {
+        result.insert(0, DIGITS[(tmp % 10) as usize]);
+        tmp /= 10;
+    }
+    if tmp > 0 {
+        for d in &mut result {
+            *d = DIGITS[9];
+        }
+    }
     result
 }
 
+// segments of a negative `n` on `digits` elements, a minus sign in front of
+// the digits of its magnitude
+fn negative_segments(n: i32, digits: usize, leading_zeros: bool) -> Vec<u32> {
+    if digits == 0 {
+        return Vec::new();
+    }
+    let max_digits = digits - 1;
+    let min_digits = if leading_zeros { max_digits } else { 1 };
+    let mut segments = number_segments((n as i64).abs() as u32, min_digits, max_digits);
+    segments.insert(0, SEG_G);
+    segments
+}
 
-fn get_segment_indices(num: u16) -> (Vec<usize>, Vec<usize>) {
-    let mut print: Vec<usize> = Vec::new();
-    let mut alpha: Vec<usize> = Vec::new();
+fn glyph_segments(c: char) -> u32 {
+    let c = match c {
+        'a'...'z' => (c as u8 - b'a' + b'A') as char,
+        _ => c,
+    };
+    let index = (c as u32).wrapping_sub(FIRST_CHAR as u32) as usize;
+    if index < GLYPHS.len() { GLYPHS[index] } else { 0 }
+}
 
-    match num {
-        0 => {
-            push_to_vec(&mut print, &[0, 1, 2, 3, 4, 5]);
-            push_to_vec(&mut alpha, &[6]);
-        }
-        1 => {
-            push_to_vec(&mut print, &[1, 2]);
-            push_to_vec(&mut alpha, &[0, 3, 4, 5, 6]);
-        }
-        2 => {
-            push_to_vec(&mut print, &[0, 1, 3, 4, 6]);
-            push_to_vec(&mut alpha, &[2, 5]);
-        }
-        3 => {
-            push_to_vec(&mut print, &[0, 1, 2, 3, 6]);
-            push_to_vec(&mut alpha, &[4, 5]);
-        }
-        4 => {
-            push_to_vec(&mut print, &[1, 2, 5, 6]);
-            push_to_vec(&mut alpha, &[0, 3, 4]);
-        }
-        5 => {
-            push_to_vec(&mut print, &[0, 2, 3, 5, 6]);
-            push_to_vec(&mut alpha, &[1, 4]);
-        }
-        6 => {
-            push_to_vec(&mut print, &[0, 2, 3, 4, 5, 6]);
-            push_to_vec(&mut alpha, &[1]);
-        }
-        7 => {
-            push_to_vec(&mut print, &[0, 1, 2]);
-            push_to_vec(&mut alpha, &[3, 4, 5, 6]);
-        }
-        8 => {
-            push_to_vec(&mut print, &[0, 1, 2, 3, 4, 5, 6]);
-        }
-        9 => {
-            push_to_vec(&mut print, &[0, 1, 2, 3, 5, 6]);
-            push_to_vec(&mut alpha, &[4]);
-        }
-        _ => {
-            push_to_vec(&mut print, &[6]);
-            push_to_vec(&mut alpha, &[0, 1, 2, 3, 4, 5]);
-        }
+fn segment_color(segments: u32, color: Color, segment: usize) -> Color {
+    if segments & (1 << segment) != 0 {
+        color
+    } else {
+        color::TRANSPARENT
     }
-
-    (print, alpha)
 }
 
-fn push_to_vec(vec: &mut Vec<usize>, slice: &[usize]) {
-    for i in slice {
-        vec.push(*i)
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pads_with_leading_zeros() {
+        assert_eq!(number_segments(42, 4, 4), [DIGITS[0], DIGITS[0], DIGITS[4], DIGITS[2]]);
+        assert_eq!(number_segments(42, 1, 4), [DIGITS[4], DIGITS[2]]);
+        assert_eq!(number_segments(0, 1, 4), [DIGITS[0]]);
+    }
+
+    #[test]
+    fn puts_a_minus_in_front_of_negative_numbers() {
+        assert_eq!(negative_segments(-42, 4, true), [SEG_G, DIGITS[0], DIGITS[4], DIGITS[2]]);
+        assert_eq!(negative_segments(-42, 4, false), [SEG_G, DIGITS[4], DIGITS[2]]);
+        assert_eq!(negative_segments(-42, 1, true), [SEG_G]);
+        assert!(negative_segments(-42, 0, true).is_empty());
+    }
+
+    #[test]
+    fn shows_all_nines_when_too_wide() {
+        let nines = [DIGITS[9]; constants::SCORE_DIGITS];
+        assert_eq!(number_segments(123_456, constants::SCORE_DIGITS, constants::SCORE_DIGITS),
+                   nines);
+        assert_eq!(number_segments(u32::max_value(), 1, constants::SCORE_DIGITS), nines);
+        assert_eq!(number_segments(99_999, 1, constants::SCORE_DIGITS), nines);
     }
 }
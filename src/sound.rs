@@ -0,0 +1,112 @@
+// embedded sound samples and their decoders
+//
+// all samples are mono at the output rate, see sounds/generate.py for the
+// formats.
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    // 16 bit little endian
+    Pcm16,
+    // ima adpcm, low nibble first, starting at predictor 0 and step index 0
+    ImaAdpcm,
+}
+
+pub struct Sample {
+    pub data: &'static [u8],
+    pub encoding: Encoding,
+}
+
+const INDEX_TABLE: [i8; 16] = [-1, -1, -1, -1, 2, 4, 6, 8, -1, -1, -1, -1, 2, 4, 6, 8];
+
+static STEP_TABLE: [i32; 89] = [
+    7, 8, 9, 10, 11, 12, 13, 14, 16, 17, 19, 21, 23, 25, 28, 31, 34, 37, 41,
+    45, 50, 55, 60, 66, 73, 80, 88, 97, 107, 118, 130, 143, 157, 173, 190, 209,
+    230, 253, 279, 307, 337, 371, 408, 449, 494, 544, 598, 658, 724, 796, 876,
+    963, 1060, 1166, 1282, 1411, 1552, 1707, 1878, 2066, 2272, 2499, 2749,
+    3024, 3327, 3660, 4026, 4428, 4871, 5358, 5894, 6484, 7132, 7845, 8630,
+    9493, 10442, 11487, 12635, 13899, 15289, 16818, 18500, 20350, 22385,
+    24623, 27086, 29794, 32767,
+];
+
+// reads one sample after the other
+pub struct Decoder {
+    sample: &'static Sample,
+    // in samples, not bytes
+    pos: usize,
+    predictor: i32,
+    step_index: usize,
+}
+
+impl Decoder {
+    pub fn new(sample: &'static Sample) -> Self {
+        Decoder {
+            sample: sample,
+            pos: 0,
+            predictor: 0,
+            step_index: 0,
+        }
+    }
+
+    pub fn rewind(&mut self) {
+        self.pos = 0;
+        self.predictor = 0;
+        self.step_index = 0;
+    }
+
+    fn decode(&mut self, code: u8) -> i16 {
+        let step = STEP_TABLE[self.step_index];
+        let mut delta = step >> 3;
+        if code & 4 != 0 {
+            delta += step;
+        }
+        if code & 2 != 0 {
+            delta += step >> 1;
+        }
+        if code & 1 != 0 {
+            delta += step >> 2;
+        }
+        self.predictor += if code & 8 != 0 { -delta } else { delta };
+        if self.predictor > i16::max_value() as i32 {
+            self.predictor = i16::max_value() as i32;
+        } else if self.predictor < i16::min_value() as i32 {
+            self.predictor = i16::min_value() as i32;
+        }
+        let index = self.step_index as i32 + INDEX_TABLE[code as usize] as i32;
+        self.step_index = if index < 0 {
+            0
+        } else if index >= STEP_TABLE.len() as i32 {
+            STEP_TABLE.len() - 1
+        } else {
+            index as usize
+        };
+        self.predictor as i16
+    }
+}
+
+impl Iterator for Decoder {
+    type Item = i16;
+
+    // none at the end of the sample
+    fn next(&mut self) -> Option<i16> {
+        let data = self.sample.data;
+        let value = match self.sample.encoding {
+            Encoding::Pcm16 => {
+                if self.pos * 2 + 1 >= data.len() {
+                    return None;
+                }
+                let (low, high) = (data[self.pos * 2] as u16, data[self.pos * 2 + 1] as u16);
+                (low | (high << 8)) as i16
+            }
+            Encoding::ImaAdpcm => {
+                if self.pos / 2 >= data.len() {
+                    return None;
+                }
+                let byte = data[self.pos / 2];
+                let code = if self.pos % 2 == 0 { byte & 0xF } else { byte >> 4 };
+                self.decode(code)
+            }
+        };
+        self.pos += 1;
+        Some(value)
+    }
+}
@@ -0,0 +1,175 @@
+// sound output through the headphone jack of the board
+//
+// dma2 feeds sai2 block a from a ring buffer in the background, the codec
+// plays it at the same rate the microphone records. the game loop mixes a
+// little ahead of the dma on every frame, far enough to cover a frame and
+// near enough that effects are heard together with what caused them.
+use core::ptr;
+use stm32f7::board::sai::Sai;
+use stm32f7::board::rcc::Rcc;
+use stm32f7::i2c::{self, I2C};
+use stm32f7::system_clock;
+use constants;
+use mixer::Mixer;
+
+// stream 4, channel 3 of dma2 serves sai2 block a (RM0385, chapter 8)
+const DMA2_BASE: u32 = 0x4002_6400;
+const HIFCR: u32 = 0x0C;
+const S4CR: u32 = 0x10 + 4 * 0x18;
+const S4NDTR: u32 = S4CR + 0x04;
+const S4PAR: u32 = S4CR + 0x08;
+const S4M0AR: u32 = S4CR + 0x0C;
+const S4FCR: u32 = S4CR + 0x14;
+// all interrupt flags of stream 4
+const HIFCR_STREAM_4: u32 = 0b11_1101;
+
+const CR_EN: u32 = 1 << 0;
+const CR_DIR_MEM_TO_PERIPH: u32 = 0b01 << 6;
+const CR_CIRC: u32 = 1 << 8;
+const CR_MINC: u32 = 1 << 10;
+const CR_PSIZE_16: u32 = 0b01 << 11;
+const CR_MSIZE_16: u32 = 0b01 << 13;
+const CR_PL_HIGH: u32 = 0b10 << 16;
+const CR_CHSEL_3: u32 = 3 << 25;
+
+// data register of sai2 block a
+const SAI2_ADR: u32 = 0x4001_5C20;
+
+// 7 bit i2c address of the wm8994 codec
+const WM8994_ADDRESS: u8 = 0b001_1010;
+
+const RING_LEN: usize = constants::SPEAKER_RING;
+static mut RING: [i16; RING_LEN] = [0; RING_LEN];
+
+// samples mixed at once
+const CHUNK: usize = 256;
+
+pub struct Speaker {
+    mixer: Mixer,
+    chunk: [i16; CHUNK],
+    write_pos: usize,
+    // position the dma read next at the last update
+    last_read: usize,
+}
+
+impl Speaker {
+    // `volume` is the one of the mixer, the codec drives the headphones with
+    // constants::HEADPHONE_VOLUME
+    pub fn new(sai: &'static Sai, rcc: &mut Rcc, i2c: &mut I2C, volume: u8) -> Self {
+        init_codec_output(i2c, constants::HEADPHONE_VOLUME);
+        rcc.ahb1enr.update(|r| r.set_dma2en(true));
+        write_reg(S4CR, 0);
+        while read_reg(S4CR) & CR_EN != 0 {}
+        write_reg(HIFCR, HIFCR_STREAM_4);
+        write_reg(S4PAR, SAI2_ADR);
+        write_reg(S4M0AR, unsafe { &RING as *const _ as u32 });
+        write_reg(S4NDTR, RING_LEN as u32);
+        // direct mode, every request moves one sample
+        write_reg(S4FCR, 0);
+        write_reg(S4CR,
+                  CR_CHSEL_3 | CR_PL_HIGH | CR_MSIZE_16 | CR_PSIZE_16 | CR_MINC | CR_CIRC |
+                  CR_DIR_MEM_TO_PERIPH | CR_EN);
+        sai.acr1.update(|r| r.set_dmaen(true));
+
+        Speaker {
+            mixer: Mixer::new(volume),
+            chunk: [0; CHUNK],
+            write_pos: 0,
+            last_read: 0,
+        }
+    }
+
+    pub fn mixer(&mut self) -> &mut Mixer {
+        &mut self.mixer
+    }
+
+    // mixes up to the lead ahead of the dma
+    pub fn update(&mut self) {
+        let read_pos = self.read_pos();
+        // silence what was played, so the dma plays silence instead of old
+        // sounds when the game loop stalls
+        while self.last_read != read_pos {
+            unsafe { ptr::write_volatile(&mut RING[self.last_read], 0) };
+            self.last_read = (self.last_read + 1) % RING_LEN;
+        }
+        let mut ahead = (self.write_pos + RING_LEN - read_pos) % RING_LEN;
+        if ahead > constants::SPEAKER_LEAD {
+            // the dma overtook us while the game loop was stalled, carry on
+            // at the next frame it has not played yet
+            let channels = constants::SPEAKER_CHANNELS;
+            self.write_pos = (read_pos + channels - 1) / channels * channels % RING_LEN;
+            ahead = (self.write_pos + RING_LEN - read_pos) % RING_LEN;
+        }
+        let mut missing = constants::SPEAKER_LEAD - ahead;
+        // whole frames only, so the channels stay in place
+        missing -= missing % constants::SPEAKER_CHANNELS;
+        while missing > 0 {
+            let len = if missing < CHUNK { missing } else { CHUNK };
+            self.mixer.mix(&mut self.chunk[..len], constants::SPEAKER_CHANNELS);
+            for i in 0..len {
+                let pos = (self.write_pos + i) % RING_LEN;
+                unsafe { ptr::write_volatile(&mut RING[pos], self.chunk[i]) };
+            }
+            self.write_pos = (self.write_pos + len) % RING_LEN;
+            missing -= len;
+        }
+    }
+
+    // position the dma reads next
+    fn read_pos(&self) -> usize {
+        // the counter runs down from the ring length and reloads at the end
+        (RING_LEN - read_reg(S4NDTR) as usize) % RING_LEN
+    }
+}
+
+// turns on the headphone output next to the microphone input that
+// audio::init_wm8994 set up: aif1 timeslot 0 feeds dac 1, which drives the
+// headphone amplifiers. follows the headphone start-up sequence of the
+// wm8994 datasheet, `volume` goes from 0 (-57 dB) to 63 (+6 dB).
+fn init_codec_output(i2c: &mut I2C, volume: u8) {
+    let volume = (volume & 0x3F) as u16;
+    i2c.connect::<u16, _>(i2c::Address::bits_7(WM8994_ADDRESS), |mut conn| {
+        // enable aif1 dac 1 and dac 1, left and right
+        conn.write(0x05, 0x0303).unwrap();
+        // aif1 timeslot 0 to dac 1, left and right
+        conn.write(0x601, 0x0001).unwrap();
+        conn.write(0x602, 0x0001).unwrap();
+        // headphone input stages, next to the bias, vmid and microphone bias
+        // that are on already
+        let power = conn.read(0x01).unwrap();
+        conn.write(0x01, power | 0x0300).unwrap();
+        // headphone intermediate stages
+        conn.write(0x60, 0x0022).unwrap();
+        // charge pump
+        conn.write(0x4C, 0x9F25).unwrap();
+        system_clock::wait(15);
+        // dac 1 straight to the headphone outputs
+        conn.write(0x2D, 0x0001).unwrap();
+        conn.write(0x2E, 0x0001).unwrap();
+        // output mixers
+        conn.write(0x03, 0x0030).unwrap();
+        // dc servo start-up, left and right
+        conn.write(0x54, 0x0033).unwrap();
+        system_clock::wait(257);
+        // headphone output stages, clamps removed
+        conn.write(0x60, 0x00EE).unwrap();
+        // unmute dac 1 at 0 dB and the aif1 timeslot 0 path
+        conn.write(0x610, 0x00C0).unwrap();
+        conn.write(0x611, 0x00C0).unwrap();
+        conn.write(0x420, 0x0000).unwrap();
+        // unmuted headphone volume, the update bit of the right channel
+        // applies both
+        conn.write(0x1C, 0x0040 | volume).unwrap();
+        conn.write(0x1D, 0x0140 | volume).unwrap();
+        Ok(())
+    })
+        .unwrap();
+}
+
+fn write_reg(offset: u32, value: u32) {
+    unsafe { ptr::write_volatile((DMA2_BASE + offset) as *mut u32, value) };
+}
+
+fn read_reg(offset: u32) -> u32 {
+    unsafe { ptr::read_volatile((DMA2_BASE + offset) as *const u32) }
+}
@@ -0,0 +1,106 @@
+// scaling and rotation of dumps
+use color::Color;
+use math;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Filter {
+    Nearest,
+    Bilinear,
+}
+
+// coordinates are mapped in 16.16 fixed point
+const FP_SHIFT: i64 = 16;
+const FP_ONE: i64 = 1 << FP_SHIFT;
+const FP_HALF: i64 = FP_ONE / 2;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Transform {
+    // size of the image after scaling, before it is rotated
+    pub size: (u16, u16),
+    // clockwise, in degrees; quarter turns are exact
+    pub angle: i32,
+    pub filter: Filter,
+}
+
+impl Transform {
+    pub fn scaled(size: (u16, u16), filter: Filter) -> Self {
+        Transform {
+            size: size,
+            angle: 0,
+            filter: filter,
+        }
+    }
+
+    pub fn rotated(size: (u16, u16), angle: i32, filter: Filter) -> Self {
+        Transform {
+            size: size,
+            angle: angle,
+            filter: filter,
+        }
+    }
+
+    // bounding box of the scaled and rotated image
+    pub fn output_size(&self) -> (u16, u16) {
+        let sin = (math::sin_deg(self.angle) as i64).abs();
+        let cos = (math::cos_deg(self.angle) as i64).abs();
+        let (w, h) = (self.size.0 as i64, self.size.1 as i64);
+        let round = (math::Q14_ONE / 2) as i64;
+        (((w * cos + h * sin + round) >> 14) as u16, ((w * sin + h * cos + round) >> 14) as u16)
+    }
+
+    // position in the source image (16.16 fixed point) that ends up at pixel
+    // (x, y) of the output box, None if that pixel is not covered
+    fn source_pos(&self, src_size: (u16, u16), x: i32, y: i32) -> Option<(i64, i64)> {
+        let out = self.output_size();
+        // pixel center relative to the center of the output box
+        let dx = ((x as i64) << FP_SHIFT) + FP_HALF - ((out.0 as i64) << (FP_SHIFT - 1));
+        let dy = ((y as i64) << FP_SHIFT) + FP_HALF - ((out.1 as i64) << (FP_SHIFT - 1));
+        // rotate back counterclockwise
+        let sin = math::sin_deg(self.angle) as i64;
+        let cos = math::cos_deg(self.angle) as i64;
+        let u = (dx * cos + dy * sin) >> 14;
+        let v = (dy * cos - dx * sin) >> 14;
+        // relative to the top left corner of the scaled image
+        let u = u + ((self.size.0 as i64) << (FP_SHIFT - 1));
+        let v = v + ((self.size.1 as i64) << (FP_SHIFT - 1));
+        if u < 0 || v < 0 || u >= (self.size.0 as i64) << FP_SHIFT ||
+           v >= (self.size.1 as i64) << FP_SHIFT {
+            return None;
+        }
+        Some((u * src_size.0 as i64 / self.size.0 as i64,
+              v * src_size.1 as i64 / self.size.1 as i64))
+    }
+
+    // color of output pixel (x, y) for a dump of `src_size`
+    pub fn sample(&self, dump: &[u8], src_size: (u16, u16), x: i32, y: i32) -> Option<Color> {
+        let (sx, sy) = match self.source_pos(src_size, x, y) {
+            Some(pos) => pos,
+            None => return None,
+        };
+        match self.filter {
+            Filter::Nearest => {
+                Some(pixel(dump, src_size, (sx >> FP_SHIFT) as i32, (sy >> FP_SHIFT) as i32))
+            }
+            Filter::Bilinear => Some(bilinear(dump, src_size, sx - FP_HALF, sy - FP_HALF)),
+        }
+    }
+}
+
+// pixel of a dump, coordinates are clamped to the image
+fn pixel(dump: &[u8], size: (u16, u16), x: i32, y: i32) -> Color {
+    let x = if x < 0 { 0 } else if x >= size.0 as i32 { size.0 as i32 - 1 } else { x };
+    let y = if y < 0 { 0 } else if y >= size.1 as i32 { size.1 as i32 - 1 } else { y };
+    let idx = (y as usize * size.0 as usize + x as usize) * 4;
+    Color::argb(dump[idx + 3], dump[idx + 2], dump[idx + 1], dump[idx])
+}
+
+fn bilinear(dump: &[u8], size: (u16, u16), sx: i64, sy: i64) -> Color {
+    let x = (sx >> FP_SHIFT) as i32;
+    let y = (sy >> FP_SHIFT) as i32;
+    // fractional parts as blend amount 0..255
+    let fx = ((sx & (FP_ONE - 1)) >> (FP_SHIFT - 8)) as u8;
+    let fy = ((sy & (FP_ONE - 1)) >> (FP_SHIFT - 8)) as u8;
+    let top = pixel(dump, size, x, y).blend(pixel(dump, size, x + 1, y), fx);
+    let bottom = pixel(dump, size, x, y + 1).blend(pixel(dump, size, x + 1, y + 1), fx);
+    top.blend(bottom, fy)
+}
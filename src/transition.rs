@@ -0,0 +1,72 @@
+// effects for switching between scenes, the old scene is a snapshot of the
+// foreground and the new one is drawn as usual while the effect runs
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Effect {
+    // fades both layers out to the lcd background color and the new scene
+    // back in
+    Fade,
+    // the new scene is uncovered from left to right
+    Wipe,
+    // the old scene is pushed out to the left by the new one
+    Slide,
+}
+
+// progress is counted in 1/256
+pub const PROGRESS_DONE: u32 = 256;
+
+#[derive(Clone, Copy)]
+pub struct Transition {
+    pub effect: Effect,
+    start: usize,
+    duration: usize,
+    progress: u32,
+}
+
+impl Transition {
+    pub fn new(effect: Effect, start: usize, duration: usize) -> Self {
+        Transition {
+            effect: effect,
+            start: start,
+            duration: duration,
+            progress: 0,
+        }
+    }
+
+    pub fn update(&mut self, tick: usize) {
+        let elapsed = tick.saturating_sub(self.start);
+        self.progress = if elapsed >= self.duration {
+            PROGRESS_DONE
+        } else {
+            (elapsed as u64 * PROGRESS_DONE as u64 / self.duration as u64) as u32
+        };
+    }
+
+    pub fn progress(&self) -> u32 {
+        self.progress
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.progress >= PROGRESS_DONE
+    }
+
+    // number of columns the effect has advanced on a `width` wide screen
+    pub fn columns(&self, width: u16) -> u16 {
+        (width as u32 * self.progress / PROGRESS_DONE) as u16
+    }
+
+    // fades show the old scene in the first half and the new one in the
+    // second half, the returned alpha scales the layer opacity
+    pub fn shows_new_scene(&self) -> bool {
+        self.effect != Effect::Fade || self.progress >= PROGRESS_DONE / 2
+    }
+
+    pub fn fade_alpha(&self) -> u8 {
+        let half = PROGRESS_DONE / 2;
+        let distance = if self.progress < half {
+            half - self.progress
+        } else {
+            self.progress - half
+        };
+        (distance * 255 / half) as u8
+    }
+}
@@ -0,0 +1,98 @@
+// minimal reader for 16 bit pcm wav files, so recorded claps can be fed to
+// the audio analysis on the host
+use core::cmp;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WavError {
+    NotRiffWave,
+    UnsupportedFormat,
+    MissingChunk,
+    Truncated,
+}
+
+pub struct Wav<'a> {
+    pub channels: u16,
+    pub sample_rate: u32,
+    data: &'a [u8],
+}
+
+impl<'a> Wav<'a> {
+    pub fn parse(bytes: &'a [u8]) -> Result<Self, WavError> {
+        if bytes.len() < 12 || &bytes[0..4] != b"RIFF" || &bytes[8..12] != b"WAVE" {
+            return Err(WavError::NotRiffWave);
+        }
+        let mut format: Option<(u16, u32)> = None;
+        let mut rest = &bytes[12..];
+        while rest.len() >= 8 {
+            let id = &rest[0..4];
+            let size = read_u32(&rest[4..8]) as usize;
+            let body = &rest[8..];
+            if body.len() < size {
+                return Err(WavError::Truncated);
+            }
+            let body = &body[..size];
+            if id == b"fmt " {
+                if size < 16 {
+                    return Err(WavError::Truncated);
+                }
+                let pcm = read_u16(&body[0..2]) == 1;
+                let bits = read_u16(&body[14..16]);
+                if !pcm || bits != 16 {
+                    return Err(WavError::UnsupportedFormat);
+                }
+                format = Some((read_u16(&body[2..4]), read_u32(&body[4..8])));
+            } else if id == b"data" {
+                return match format {
+                    Some((channels, sample_rate)) => {
+                        Ok(Wav {
+                            channels: channels,
+                            sample_rate: sample_rate,
+                            data: body,
+                        })
+                    }
+                    None => Err(WavError::MissingChunk),
+                };
+            }
+            // chunks are padded to an even size
+            let next = 8 + size + (size & 1);
+            rest = &rest[cmp::min(next, rest.len())..];
+        }
+        Err(WavError::MissingChunk)
+    }
+
+    // number of samples of all channels together
+    pub fn len(&self) -> usize {
+        self.data.len() / 2
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // samples are interleaved like the ones of the microphone
+    pub fn sample(&self, index: usize) -> i16 {
+        read_u16(&self.data[index * 2..index * 2 + 2]) as i16
+    }
+
+    // copies the samples from `start` into `window` and returns how many
+    // there were
+    pub fn read(&self, start: usize, window: &mut [i16]) -> usize {
+        let mut count = 0;
+        for (i, s) in window.iter_mut().enumerate() {
+            if start + i >= self.len() {
+                break;
+            }
+            *s = self.sample(start + i);
+            count += 1;
+        }
+        count
+    }
+}
+
+fn read_u16(bytes: &[u8]) -> u16 {
+    (bytes[0] as u16) | ((bytes[1] as u16) << 8)
+}
+
+fn read_u32(bytes: &[u8]) -> u32 {
+    (read_u16(&bytes[0..2]) as u32) | ((read_u16(&bytes[2..4]) as u32) << 16)
+}
@@ -0,0 +1,90 @@
+#!/usr/bin/env python3
+# -*- coding: utf-8 -*-
+
+# synthesizes the recordings the audio analysis is tested with
+#
+# all recordings are stereo 16 bit pcm wav files at 16 kHz like the samples
+# of the microphone. they start with half a second of room noise, so the
+# tests can measure the noise floor from it.
+
+import math
+import random
+import struct
+
+RATE = 16000
+CHANNELS = 2
+# rms of the room noise
+NOISE = 200
+
+
+def clamp(value, low, high):
+    return max(low, min(high, value))
+
+
+def write_wav(name, frames):
+    data = b"".join(struct.pack("<hh", clamp(int(l), -32768, 32767),
+                                clamp(int(r), -32768, 32767)) for l, r in frames)
+    with open(name + ".wav", "wb") as out:
+        out.write(b"RIFF" + struct.pack("<I", 36 + len(data)) + b"WAVE")
+        out.write(b"fmt " + struct.pack("<IHHIIHH", 16, 1, CHANNELS, RATE,
+                                        RATE * CHANNELS * 2, CHANNELS * 2, 16))
+        out.write(b"data" + struct.pack("<I", len(data)) + data)
+
+
+def room(seconds):
+    return [(random.gauss(0, NOISE), random.gauss(0, NOISE))
+            for _ in range(int(seconds * RATE))]
+
+
+# a clap is a burst of white noise that dies away within a few ms
+def clap(frames, at, volume=0.6, decay=0.008):
+    start = int(at * RATE)
+    for i in range(int(decay * 8 * RATE)):
+        if start + i >= len(frames):
+            break
+        gain = volume * 32767 * math.exp(-i / (decay * RATE))
+        l, r = frames[start + i]
+        frames[start + i] = (l + gain * random.uniform(-1, 1),
+                             r + gain * random.uniform(-1, 1))
+    return frames
+
+
+# a whistle is a pure tone
+def whistle(frames, at, freq, seconds, volume=0.3):
+    return add(frames, at, seconds, volume, lambda t: math.sin(2 * math.pi * freq * t))
+
+
+# a sustained "ahh": harmonics of the voice, loudest around the formants of
+# the vowel
+def voice(frames, at, pitch, seconds, volume=0.3):
+    formants = (700, 1200)
+    harmonics = [(n * pitch, sum(1.0 / (1 + ((n * pitch - f) / 150.0) ** 2) for f in formants))
+                 for n in range(1, int(3000 / pitch))]
+    total = sum(a for _, a in harmonics)
+    return add(frames, at, seconds, volume,
+               lambda t: sum(a * math.sin(2 * math.pi * f * t) for f, a in harmonics) / total)
+
+
+def add(frames, at, seconds, volume, wave):
+    start = int(at * RATE)
+    length = int(seconds * RATE)
+    # fade in and out within 10 ms
+    fade = 0.01 * RATE
+    for i in range(length):
+        gain = volume * 32767 * min(1.0, i / fade, (length - i) / fade)
+        s = gain * wave(i / float(RATE))
+        l, r = frames[start + i]
+        frames[start + i] = (l + s, r + s)
+    return frames
+
+
+random.seed(2017)
+write_wav("noise", room(1.0))
+frames = room(1.6)
+for at in (0.5, 0.9, 1.3):
+    frames = clap(frames, at)
+write_wav("claps", frames)
+write_wav("whistle", whistle(room(1.2), 0.5, 1000, 0.4))
+write_wav("voice", voice(room(1.4), 0.5, 140, 0.6))
+
+print("Done")